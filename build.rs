@@ -0,0 +1,21 @@
+// regenerate the C header of the `ffi` module with `cargo build --features header`, a plain
+// build leaves the checked-in `include/rummy_app.h` alone
+#[cfg(feature = "header")]
+fn main() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("set by cargo");
+
+    match cbindgen::generate(&crate_dir) {
+        Ok(bindings) => {
+            bindings.write_to_file("include/rummy_app.h");
+        }
+        Err(e) => println!("cargo:warning=Cannot generate include/rummy_app.h: {}", e),
+    }
+}
+
+#[cfg(not(feature = "header"))]
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,155 @@
+#ifndef RUMMY_APP_H
+#define RUMMY_APP_H
+
+/* Generated by cbindgen from src/ffi.rs with `cargo build --features header`, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define RUMMY_BLACK 0
+
+#define RUMMY_BLUE 1
+
+#define RUMMY_ORANGE 2
+
+#define RUMMY_RED 3
+
+typedef enum RummyStatus {
+  RUMMY_STATUS_OK = 0,
+  /**
+   * The board has no arrangement of every tile into valid sets.
+   */
+  RUMMY_STATUS_NOT_SOLVED = 1,
+  RUMMY_STATUS_NULL_POINTER = -1,
+  /**
+   * A color other than `RUMMY_BLACK`..`RUMMY_RED`, a number outside 1-13, or a flag other
+   * than 0 or 1.
+   */
+  RUMMY_STATUS_INVALID_TILE = -2,
+  /**
+   * No set or tile at that index.
+   */
+  RUMMY_STATUS_INVALID_INDEX = -3,
+  /**
+   * An internal error, the handle is unchanged.
+   */
+  RUMMY_STATUS_PANIC = -4,
+} RummyStatus;
+
+/**
+ * A board, set 0 is the hand and every other set lies on the table.
+ */
+typedef struct RummyBoard RummyBoard;
+
+/**
+ * The sets a solve arranged every tile into.
+ */
+typedef struct RummySolution RummySolution;
+
+/**
+ * One tile, `is_joker` and `is_pinned` are 0 or 1. A joker with `number` 0 may stand in for
+ * any tile, one with a number and `is_pinned` only for exactly that tile. A joker with a
+ * number that is not pinned is still free, the number is only a guess the solver may change.
+ * Jokers in a solution carry their assigned value.
+ */
+typedef struct RummyTile {
+  uint8_t number;
+  uint8_t color;
+  uint8_t is_joker;
+  uint8_t is_pinned;
+} RummyTile;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * A new board with an empty hand, or null if it could not be created.
+ */
+struct RummyBoard *rummy_board_new(void);
+
+/**
+ * # Safety
+ * `board` must be null or a handle from `rummy_board_new` that is not used afterwards.
+ */
+void rummy_board_free(struct RummyBoard *board);
+
+/**
+ * Add an empty set to the table and write its index to `index`, which may be null.
+ *
+ * # Safety
+ * `board` must be a live handle and `index` null or valid for writes.
+ */
+enum RummyStatus rummy_board_add_set(struct RummyBoard *board, size_t *index);
+
+/**
+ * Add a tile to the set at `set`, 0 being the hand.
+ *
+ * # Safety
+ * `board` must be a live handle.
+ */
+enum RummyStatus rummy_board_add_tile(struct RummyBoard *board, size_t set, struct RummyTile tile);
+
+/**
+ * The number of sets including the hand, 0 for a null board.
+ *
+ * # Safety
+ * `board` must be null or a live handle.
+ */
+size_t rummy_board_set_count(const struct RummyBoard *board);
+
+/**
+ * Arrange every tile of the board, hand and table, into valid sets. On `RUMMY_STATUS_OK`
+ * `solution` receives a handle to free with `rummy_solution_free`, otherwise it is set to null.
+ *
+ * # Safety
+ * `board` must be a live handle and `solution` valid for writes.
+ */
+enum RummyStatus rummy_board_solve(const struct RummyBoard *board, struct RummySolution **solution);
+
+/**
+ * # Safety
+ * `solution` must be null or a handle from `rummy_board_solve` that is not used afterwards.
+ */
+void rummy_solution_free(struct RummySolution *solution);
+
+/**
+ * The number of sets in the solution, 0 for a null solution.
+ *
+ * # Safety
+ * `solution` must be null or a live handle.
+ */
+size_t rummy_solution_set_count(const struct RummySolution *solution);
+
+/**
+ * The number of tiles in the set at `set`, 0 if there is no such set.
+ *
+ * # Safety
+ * `solution` must be null or a live handle.
+ */
+size_t rummy_solution_set_len(const struct RummySolution *solution, size_t set);
+
+/**
+ * Write the tile at `position` of the set at `set` to `tile`.
+ *
+ * # Safety
+ * `solution` must be a live handle and `tile` valid for writes.
+ */
+enum RummyStatus rummy_solution_tile(const struct RummySolution *solution,
+                                     size_t set,
+                                     size_t position,
+                                     struct RummyTile *tile);
+
+/**
+ * A static, nul-terminated description of `status`, a `RummyStatus` value.
+ */
+const char *rummy_status_message(int status);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RUMMY_APP_H */
@@ -0,0 +1,313 @@
+use crate::server::ServeOptions;
+use rummy_app::{Game, NotationError, Solver, Tile, TileSet};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::process::ExitCode;
+use std::time::Duration;
+
+pub const SOLVED: u8 = 0;
+pub const NOT_SOLVED: u8 = 1;
+pub const INVALID_INPUT: u8 = 2;
+
+pub const USAGE: &str = "Usage:
+    rummy-app                                       Start the terminal UI.
+    rummy-app solve [--hand TILES] [--table FILE]   Solve a board and print one set per line.
+    rummy-app repl                                  Read commands from stdin, print the board as text.
+    rummy-app json                                  Answer one JSON request per line of stdin.
+    rummy-app serve [--port PORT] [--timeout SECS]  Serve /solve, /validate and /parse on localhost.
+
+Options:
+    --hand TILES    The tiles in your hand, e.g. \"r3 r4 b10 w\" or \"r:3,4,5 b:10\".
+    --table FILE    The sets on the table, one per line or separated by ';'. Use '-' for
+                    stdin, which is also read when neither option is given and stdin is not
+                    a terminal.
+    --port PORT     The port to listen on, 7878 by default.
+    --timeout SECS  How long one /solve may search, 10 seconds by default.
+    -h, --help      Print this help.
+
+Exit status: 0 solved, 1 no solution, 2 invalid input.";
+
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    Io { path: String, error: io::Error },
+    Hand(NotationError),
+    Table { path: String, error: NotationError },
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) => write!(f, "{}\n\n{}", msg, USAGE),
+            CliError::Io { path, error } => write!(f, "Cannot read {}: {}", path, error),
+            CliError::Hand(e) => write!(f, "Invalid hand: {}", e),
+            CliError::Table { path, error } => write!(f, "Invalid table in {}: {}", path, error),
+        }
+    }
+}
+
+impl Error for CliError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Tui,
+    Help,
+    Solve(SolveOptions),
+    Repl,
+    Json,
+    Serve(ServeOptions),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SolveOptions {
+    pub hand: Option<String>,
+    /// A file path, or `-` for stdin.
+    pub table: Option<String>,
+}
+
+/// Parse the arguments after the program name.
+pub fn parse_args(args: &[String]) -> Result<Mode, CliError> {
+    let mut args = args.iter();
+
+    match args.next().map(String::as_str) {
+        None => Ok(Mode::Tui),
+        Some("-h" | "--help" | "help") => Ok(Mode::Help),
+        Some("solve") => {
+            let mut options = SolveOptions::default();
+
+            while let Some(arg) = args.next() {
+                let slot = match arg.as_str() {
+                    "--hand" => &mut options.hand,
+                    "--table" => &mut options.table,
+                    "-h" | "--help" => return Ok(Mode::Help),
+                    other => return Err(CliError::Usage(format!("Unknown option '{}'!", other))),
+                };
+
+                let value = args
+                    .next()
+                    .ok_or_else(|| CliError::Usage(format!("Option '{}' needs a value!", arg)))?;
+                *slot = Some(value.clone());
+            }
+
+            Ok(Mode::Solve(options))
+        }
+        Some(command @ ("repl" | "json")) => match args.next() {
+            None if command == "repl" => Ok(Mode::Repl),
+            None => Ok(Mode::Json),
+            Some(other) => Err(CliError::Usage(format!("Unknown option '{}'!", other))),
+        },
+        Some("serve") => {
+            let mut options = ServeOptions::default();
+
+            while let Some(arg) = args.next() {
+                if arg == "-h" || arg == "--help" {
+                    return Ok(Mode::Help);
+                }
+
+                let value = args
+                    .next()
+                    .ok_or_else(|| CliError::Usage(format!("Option '{}' needs a value!", arg)))?;
+                let invalid =
+                    || CliError::Usage(format!("Invalid value '{}' for '{}'!", value, arg));
+
+                match arg.as_str() {
+                    "--port" => options.port = value.parse().map_err(|_| invalid())?,
+                    "--timeout" => match value.parse::<u64>() {
+                        Ok(secs) if secs > 0 => options.timeout = Duration::from_secs(secs),
+                        _ => return Err(invalid()),
+                    },
+                    other => return Err(CliError::Usage(format!("Unknown option '{}'!", other))),
+                }
+            }
+
+            Ok(Mode::Serve(options))
+        }
+        Some(other) => Err(CliError::Usage(format!("Unknown command '{}'!", other))),
+    }
+}
+
+/// Solve the board given by the options, print the sets to stdout and return the exit status.
+pub fn solve(options: &SolveOptions) -> ExitCode {
+    let result = read_table(options)
+        .and_then(|(path, table)| build_game(options.hand.as_deref().unwrap_or(""), &path, &table));
+
+    let game = match result {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(INVALID_INPUT);
+        }
+    };
+
+    match Solver::new(game).solve().into_sets() {
+        Some(sets) => {
+            print!("{}", format_sets(&sets));
+            ExitCode::from(SOLVED)
+        }
+        None => {
+            eprintln!("No solution, not every tile fits into a valid set.");
+            ExitCode::from(NOT_SOLVED)
+        }
+    }
+}
+
+// the path (for error messages) and the text of the table
+fn read_table(options: &SolveOptions) -> Result<(String, String), CliError> {
+    let read_stdin = || {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .map(|_| ("stdin".to_string(), input))
+            .map_err(|error| CliError::Io {
+                path: "stdin".to_string(),
+                error,
+            })
+    };
+
+    match options.table.as_deref() {
+        Some("-") => read_stdin(),
+        Some(path) => fs::read_to_string(path)
+            .map(|input| (path.to_string(), input))
+            .map_err(|error| CliError::Io {
+                path: path.to_string(),
+                error,
+            }),
+        // a hand alone is a complete puzzle, stdin may be a pipe that never closes
+        None if options.hand.is_some() => Ok(("the table".to_string(), String::new())),
+        None if !io::stdin().is_terminal() => read_stdin(),
+        None => Err(CliError::Usage(
+            "Nothing to solve, give --hand and/or --table!".to_string(),
+        )),
+    }
+}
+
+/// The hand becomes row 0 and every non-empty set of the table one row after it.
+pub fn build_game(hand: &str, path: &str, table: &str) -> Result<Game, CliError> {
+    let hand = hand.parse::<TileSet>().map_err(CliError::Hand)?;
+    let table = table.parse::<Game>().map_err(|error| CliError::Table {
+        path: path.to_string(),
+        error,
+    })?;
+
+    let mut board = vec![hand.0];
+    board.extend(table.board.into_iter().filter(|set| !set.is_empty()));
+
+    Ok(Game::new_with_board(board))
+}
+
+/// One set per line in the text notation, so the output can be read back with `--table`.
+pub fn format_sets(sets: &[Vec<Tile>]) -> String {
+    sets.iter()
+        .map(|set| format!("{}\n", TileSet(set.clone())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(input: &str) -> Vec<String> {
+        input.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(&[]).unwrap(), Mode::Tui);
+        assert_eq!(parse_args(&args("--help")).unwrap(), Mode::Help);
+        assert_eq!(parse_args(&args("solve -h")).unwrap(), Mode::Help);
+
+        let mode = parse_args(&[
+            "solve".to_string(),
+            "--hand".to_string(),
+            "r3 r4 r5".to_string(),
+            "--table".to_string(),
+            "table.txt".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(
+            mode,
+            Mode::Solve(SolveOptions {
+                hand: Some("r3 r4 r5".to_string()),
+                table: Some("table.txt".to_string()),
+            })
+        );
+
+        assert_eq!(
+            parse_args(&args("solve")).unwrap(),
+            Mode::Solve(SolveOptions::default())
+        );
+
+        assert_eq!(parse_args(&args("repl")).unwrap(), Mode::Repl);
+        assert_eq!(parse_args(&args("json")).unwrap(), Mode::Json);
+
+        assert_eq!(
+            parse_args(&args("serve --port 9000 --timeout 3")).unwrap(),
+            Mode::Serve(ServeOptions {
+                port: 9000,
+                timeout: Duration::from_secs(3),
+            })
+        );
+
+        for input in [
+            "solve --hand",
+            "solve --deck x",
+            "play",
+            "repl x",
+            "json x",
+            "serve --port",
+            "serve --port 70000",
+            "serve --timeout 0",
+            "serve --host 0.0.0.0",
+        ] {
+            assert!(
+                matches!(parse_args(&args(input)), Err(CliError::Usage(_))),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_game() {
+        let game = build_game("r3 w", "table.txt", "b10 h10 o10\n\nr5 r6 r7; b1 b2 b3\n").unwrap();
+
+        assert_eq!(game.to_string(), "r3 w; b10 h10 o10; r5 r6 r7; b1 b2 b3");
+
+        let game = build_game("", "stdin", "").unwrap();
+        assert_eq!(game.board, vec![vec![]]);
+
+        assert!(matches!(
+            build_game("r3 x", "table.txt", ""),
+            Err(CliError::Hand(_))
+        ));
+
+        let e = build_game("r3", "table.txt", "r1 r2 r3\nb0").unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("Invalid table in table.txt: Line 2: Column 2:"));
+    }
+
+    #[test]
+    fn test_format_sets() {
+        let game = build_game("r3 r4 w", "table.txt", "b10 h10 o10").unwrap();
+        let sets = Solver::new(game).solve().into_sets().expect("should solve");
+        let output = format_sets(&sets);
+
+        assert_eq!(output.lines().count(), 2);
+
+        // the joker says which tile it stands for
+        let joker = sets.concat().into_iter().find(|t| t.is_wildcard).unwrap();
+        assert!([2, 5].contains(&joker.number), "{}", output);
+        assert!(
+            output.contains(&format!("r(w~{})", joker.number)),
+            "{}",
+            output
+        );
+
+        // the output is the solved table again
+        let table = build_game("", "stdin", &output).unwrap();
+        assert_eq!(table.board[1..], sets[..]);
+    }
+}
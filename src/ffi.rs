@@ -0,0 +1,443 @@
+//! C interface for native callers, declared in `include/rummy_app.h`.
+//!
+//! Boards and solutions are opaque handles that the caller frees with `rummy_board_free` and
+//! `rummy_solution_free`. Every function reports failure through a `RummyStatus` instead of
+//! panicking across the boundary. A handle must not be used from two threads at once.
+
+use crate::game::{tile::Tile, tile_color::TileColor, Game};
+use crate::solver::Solver;
+use std::ffi::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+pub const RUMMY_BLACK: u8 = 0;
+pub const RUMMY_BLUE: u8 = 1;
+pub const RUMMY_ORANGE: u8 = 2;
+pub const RUMMY_RED: u8 = 3;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RummyStatus {
+    Ok = 0,
+    /// The board has no arrangement of every tile into valid sets.
+    NotSolved = 1,
+    NullPointer = -1,
+    /// A color other than `RUMMY_BLACK`..`RUMMY_RED`, a number outside 1-13, or a flag other
+    /// than 0 or 1.
+    InvalidTile = -2,
+    /// No set or tile at that index.
+    InvalidIndex = -3,
+    /// An internal error, the handle is unchanged.
+    Panic = -4,
+}
+
+/// One tile, `is_joker` and `is_pinned` are 0 or 1. A joker with `number` 0 may stand in for
+/// any tile, one with a number and `is_pinned` only for exactly that tile. A joker with a
+/// number that is not pinned is still free, the number is only a guess the solver may change.
+/// Jokers in a solution carry their assigned value.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RummyTile {
+    pub number: u8,
+    pub color: u8,
+    pub is_joker: u8,
+    pub is_pinned: u8,
+}
+
+/// A board, set 0 is the hand and every other set lies on the table.
+pub struct RummyBoard {
+    game: Game,
+}
+
+/// The sets a solve arranged every tile into.
+pub struct RummySolution {
+    sets: Vec<Vec<Tile>>,
+}
+
+impl RummyTile {
+    fn to_tile(self) -> Option<Tile> {
+        let color = match self.color {
+            RUMMY_BLACK => TileColor::Black,
+            RUMMY_BLUE => TileColor::Blue,
+            RUMMY_ORANGE => TileColor::Orange,
+            RUMMY_RED => TileColor::Red,
+            _ => return None,
+        };
+
+        let flag = |value: u8| match value {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        };
+
+        match (self.number, flag(self.is_joker)?, flag(self.is_pinned)?) {
+            (0, true, false) => Some(Tile::new(251, TileColor::Red, true)),
+            (1..=13, true, true) => Some(Tile::new_pinned(self.number, color)),
+            (1..=13, is_joker, false) => Some(Tile::new(self.number, color, is_joker)),
+            _ => None,
+        }
+    }
+
+    fn from_tile(tile: &Tile) -> Self {
+        RummyTile {
+            // an unassigned joker
+            number: if tile.number > 13 { 0 } else { tile.number },
+            color: tile.color.to_rank() as u8,
+            is_joker: tile.is_wildcard as u8,
+            is_pinned: tile.is_pinned as u8,
+        }
+    }
+}
+
+// a panic must not unwind into the caller
+fn guard(f: impl FnOnce() -> RummyStatus) -> RummyStatus {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(RummyStatus::Panic)
+}
+
+/// A new board with an empty hand, or null if it could not be created.
+#[no_mangle]
+pub extern "C" fn rummy_board_new() -> *mut RummyBoard {
+    catch_unwind(|| Box::into_raw(Box::new(RummyBoard { game: Game::new() })))
+        .unwrap_or(ptr::null_mut())
+}
+
+/// # Safety
+/// `board` must be null or a handle from `rummy_board_new` that is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rummy_board_free(board: *mut RummyBoard) {
+    if !board.is_null() {
+        drop(Box::from_raw(board));
+    }
+}
+
+/// Add an empty set to the table and write its index to `index`, which may be null.
+///
+/// # Safety
+/// `board` must be a live handle and `index` null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rummy_board_add_set(
+    board: *mut RummyBoard,
+    index: *mut usize,
+) -> RummyStatus {
+    let Some(board) = board.as_mut() else {
+        return RummyStatus::NullPointer;
+    };
+
+    guard(|| {
+        board.game.board.push(Vec::new());
+
+        if let Some(index) = index.as_mut() {
+            *index = board.game.board.len() - 1;
+        }
+
+        RummyStatus::Ok
+    })
+}
+
+/// Add a tile to the set at `set`, 0 being the hand.
+///
+/// # Safety
+/// `board` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn rummy_board_add_tile(
+    board: *mut RummyBoard,
+    set: usize,
+    tile: RummyTile,
+) -> RummyStatus {
+    let Some(board) = board.as_mut() else {
+        return RummyStatus::NullPointer;
+    };
+
+    guard(|| {
+        let Some(tile) = tile.to_tile() else {
+            return RummyStatus::InvalidTile;
+        };
+
+        match board.game.board.get_mut(set) {
+            Some(row) => {
+                row.push(tile);
+                RummyStatus::Ok
+            }
+            None => RummyStatus::InvalidIndex,
+        }
+    })
+}
+
+/// The number of sets including the hand, 0 for a null board.
+///
+/// # Safety
+/// `board` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn rummy_board_set_count(board: *const RummyBoard) -> usize {
+    board.as_ref().map_or(0, |board| board.game.board.len())
+}
+
+/// Arrange every tile of the board, hand and table, into valid sets. On `RUMMY_STATUS_OK`
+/// `solution` receives a handle to free with `rummy_solution_free`, otherwise it is set to null.
+///
+/// # Safety
+/// `board` must be a live handle and `solution` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rummy_board_solve(
+    board: *const RummyBoard,
+    solution: *mut *mut RummySolution,
+) -> RummyStatus {
+    let (Some(board), Some(solution)) = (board.as_ref(), solution.as_mut()) else {
+        return RummyStatus::NullPointer;
+    };
+
+    *solution = ptr::null_mut();
+
+    guard(
+        || match Solver::new(board.game.clone()).solve().into_sets() {
+            Some(sets) => {
+                *solution = Box::into_raw(Box::new(RummySolution { sets }));
+                RummyStatus::Ok
+            }
+            None => RummyStatus::NotSolved,
+        },
+    )
+}
+
+/// # Safety
+/// `solution` must be null or a handle from `rummy_board_solve` that is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rummy_solution_free(solution: *mut RummySolution) {
+    if !solution.is_null() {
+        drop(Box::from_raw(solution));
+    }
+}
+
+/// The number of sets in the solution, 0 for a null solution.
+///
+/// # Safety
+/// `solution` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn rummy_solution_set_count(solution: *const RummySolution) -> usize {
+    solution.as_ref().map_or(0, |solution| solution.sets.len())
+}
+
+/// The number of tiles in the set at `set`, 0 if there is no such set.
+///
+/// # Safety
+/// `solution` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn rummy_solution_set_len(
+    solution: *const RummySolution,
+    set: usize,
+) -> usize {
+    solution
+        .as_ref()
+        .and_then(|solution| solution.sets.get(set))
+        .map_or(0, Vec::len)
+}
+
+/// Write the tile at `position` of the set at `set` to `tile`.
+///
+/// # Safety
+/// `solution` must be a live handle and `tile` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rummy_solution_tile(
+    solution: *const RummySolution,
+    set: usize,
+    position: usize,
+    tile: *mut RummyTile,
+) -> RummyStatus {
+    let (Some(solution), Some(tile)) = (solution.as_ref(), tile.as_mut()) else {
+        return RummyStatus::NullPointer;
+    };
+
+    match solution.sets.get(set).and_then(|set| set.get(position)) {
+        Some(found) => {
+            *tile = RummyTile::from_tile(found);
+            RummyStatus::Ok
+        }
+        None => RummyStatus::InvalidIndex,
+    }
+}
+
+/// A static, nul-terminated description of `status`, a `RummyStatus` value.
+#[no_mangle]
+pub extern "C" fn rummy_status_message(status: c_int) -> *const c_char {
+    // any int can come from C, only the values of `RummyStatus` are known
+    let message: &'static [u8] = match status {
+        0 => b"ok\0",
+        1 => b"not every tile fits into a valid set\0",
+        -1 => b"a required pointer is null\0",
+        -2 => b"invalid tile, the color must be 0-3, the number 1-13 and the flags 0 or 1\0",
+        -3 => b"no set or tile at that index\0",
+        -4 => b"internal error\0",
+        _ => b"unknown status\0",
+    };
+
+    message.as_ptr().cast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn tile(number: u8, color: u8) -> RummyTile {
+        RummyTile {
+            number,
+            color,
+            is_joker: 0,
+            is_pinned: 0,
+        }
+    }
+
+    const JOKER: RummyTile = RummyTile {
+        number: 0,
+        color: RUMMY_RED,
+        is_joker: 1,
+        is_pinned: 0,
+    };
+
+    #[test]
+    fn test_solve() {
+        unsafe {
+            let board = rummy_board_new();
+            let mut set = 0;
+
+            assert_eq!(rummy_board_add_set(board, &mut set), RummyStatus::Ok);
+            assert_eq!(set, 1);
+            assert_eq!(rummy_board_set_count(board), 2);
+
+            for number in [10, 11, 12] {
+                let status = rummy_board_add_tile(board, set, tile(number, RUMMY_BLUE));
+                assert_eq!(status, RummyStatus::Ok);
+            }
+
+            rummy_board_add_tile(board, 0, tile(5, RUMMY_ORANGE));
+            rummy_board_add_tile(board, 0, tile(6, RUMMY_ORANGE));
+            rummy_board_add_tile(board, 0, JOKER);
+
+            let mut solution = ptr::null_mut();
+            assert_eq!(rummy_board_solve(board, &mut solution), RummyStatus::Ok);
+            assert_eq!(rummy_solution_set_count(solution), 2);
+
+            let mut tiles = Vec::new();
+
+            for set in 0..rummy_solution_set_count(solution) {
+                for position in 0..rummy_solution_set_len(solution, set) {
+                    let mut found = JOKER;
+                    let status = rummy_solution_tile(solution, set, position, &mut found);
+                    assert_eq!(status, RummyStatus::Ok);
+                    tiles.push(found);
+                }
+            }
+
+            assert_eq!(tiles.len(), 6);
+            // the joker comes back with the value it was assigned
+            let joker = tiles.iter().find(|tile| tile.is_joker == 1).unwrap();
+            assert!((1..=13).contains(&joker.number));
+
+            let mut found = JOKER;
+            let status = rummy_solution_tile(solution, 2, 0, &mut found);
+            assert_eq!(status, RummyStatus::InvalidIndex);
+
+            rummy_solution_free(solution);
+            rummy_board_free(board);
+
+            // an unpinned joker with a number is free, 9 does not stop it from completing 5, 6
+            let board = rummy_board_new();
+            rummy_board_add_tile(board, 0, tile(5, RUMMY_ORANGE));
+            rummy_board_add_tile(board, 0, tile(6, RUMMY_ORANGE));
+            rummy_board_add_tile(board, 0, RummyTile { number: 9, ..JOKER });
+
+            let mut solution = ptr::null_mut();
+            assert_eq!(rummy_board_solve(board, &mut solution), RummyStatus::Ok);
+
+            rummy_solution_free(solution);
+            rummy_board_free(board);
+        }
+    }
+
+    #[test]
+    fn test_header() {
+        // the checked-in header is only regenerated with `--features header`
+        let header = include_str!("../include/rummy_app.h");
+        let names = include_str!("ffi.rs")
+            .split("extern \"C\" fn ")
+            .skip(1)
+            .map(|rest| rest.split('(').next().unwrap_or_default());
+
+        for name in names {
+            assert!(
+                header.contains(&format!(" *{}(", name)) || header.contains(&format!(" {}(", name)),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        unsafe {
+            let board = rummy_board_new();
+
+            for invalid in [
+                tile(0, RUMMY_RED),
+                tile(14, RUMMY_RED),
+                tile(5, 4),
+                RummyTile {
+                    is_pinned: 1,
+                    ..tile(5, RUMMY_RED)
+                },
+                RummyTile {
+                    is_pinned: 1,
+                    ..JOKER
+                },
+                RummyTile {
+                    is_joker: 2,
+                    ..tile(5, RUMMY_RED)
+                },
+            ] {
+                let status = rummy_board_add_tile(board, 0, invalid);
+                assert_eq!(status, RummyStatus::InvalidTile, "{:?}", invalid);
+            }
+
+            let status = rummy_board_add_tile(board, 1, tile(5, RUMMY_RED));
+            assert_eq!(status, RummyStatus::InvalidIndex);
+
+            rummy_board_add_tile(board, 0, tile(5, RUMMY_RED));
+
+            let mut solution = ptr::null_mut();
+            assert_eq!(
+                rummy_board_solve(board, &mut solution),
+                RummyStatus::NotSolved
+            );
+            assert!(solution.is_null());
+
+            let status = rummy_board_add_tile(ptr::null_mut(), 0, tile(5, RUMMY_RED));
+            assert_eq!(status, RummyStatus::NullPointer);
+            assert_eq!(rummy_board_set_count(ptr::null()), 0);
+            assert_eq!(
+                rummy_board_solve(board, ptr::null_mut()),
+                RummyStatus::NullPointer
+            );
+
+            rummy_board_free(board);
+            rummy_board_free(ptr::null_mut());
+        }
+
+        let message = |status| unsafe { CStr::from_ptr(rummy_status_message(status)) };
+        assert_eq!(
+            message(RummyStatus::NotSolved as c_int).to_str().unwrap(),
+            "not every tile fits into a valid set"
+        );
+        assert_eq!(message(7).to_str().unwrap(), "unknown status");
+
+        for status in [
+            RummyStatus::Ok,
+            RummyStatus::NotSolved,
+            RummyStatus::NullPointer,
+            RummyStatus::InvalidTile,
+            RummyStatus::InvalidIndex,
+            RummyStatus::Panic,
+        ] {
+            assert_ne!(message(status as c_int).to_str().unwrap(), "unknown status");
+        }
+    }
+}
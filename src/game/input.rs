@@ -0,0 +1,126 @@
+use super::journal::Journal;
+use super::parser::{
+    command_sequence_to_operations, commands_to_tile_commands, parse_hand, parse_player,
+};
+use super::player::PlayerCommand;
+use super::tile_command::TileCommandError;
+use super::{Game, GameOperation};
+
+/// What one input line did, so it can be recorded in a `Journal`.
+#[derive(Debug, Clone)]
+pub enum Applied {
+    Operations(Vec<GameOperation>),
+    Player(PlayerCommand),
+}
+
+/// Apply one line of `hand`, board or player commands, staged while a turn is open.
+/// A failing line leaves the game unchanged.
+pub fn apply_input(game: &mut Game, input: &str) -> Result<Applied, TileCommandError> {
+    if let Some(capture) = parse_player(input) {
+        let command = capture.as_player_command()?;
+        game.apply_player_command(command.clone())?;
+
+        return Ok(Applied::Player(command));
+    }
+
+    let operations = match parse_hand(input) {
+        Some(result) => {
+            let commands = result.map_err(TileCommandError::Syntax)?;
+
+            commands_to_tile_commands(&commands, game)?
+                .into_iter()
+                .map(GameOperation::from)
+                .collect::<Vec<_>>()
+        }
+        None => command_sequence_to_operations(input, game)?,
+    };
+
+    // apply every operation to a copy first, so a failing one leaves the game unchanged
+    let mut next = game.clone();
+
+    for operation in operations.clone() {
+        if next.is_turn_started() {
+            next.stage(operation)?;
+        } else {
+            next.operate(operation)?;
+        }
+    }
+
+    *game = next;
+
+    Ok(Applied::Operations(operations))
+}
+
+/// Apply one input line and record it in the journal. `begin`, `commit` and `rollback` open
+/// and close a turn, a rolled back or failed turn leaves the journal as it was at `begin`.
+pub fn execute_input(
+    game: &mut Game,
+    journal: &mut Journal,
+    input: &str,
+) -> Result<(), TileCommandError> {
+    // the turn keywords ignore case like every other command
+    let keyword = input.trim().to_ascii_lowercase();
+
+    match keyword.as_str() {
+        "begin" => {
+            game.begin_turn()?;
+            journal.begin_turn();
+        }
+        "commit" | "rollback" => {
+            let is_turn_started = game.is_turn_started();
+            let result = if keyword == "commit" {
+                game.commit_turn()
+            } else {
+                game.rollback_turn()
+            };
+
+            // a failed commit rolls the game back, so the staged commands leave the journal too
+            if is_turn_started && (keyword == "rollback" || result.is_err()) {
+                journal.rollback_turn();
+            }
+
+            result?;
+        }
+        _ => match apply_input(game, input)? {
+            Applied::Operations(operations) => journal.record(input, operations),
+            Applied::Player(command) => journal.record_player(input, command),
+        },
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn() {
+        let mut game = Game::new();
+        let mut journal = Journal::new();
+
+        for input in ["hand r3 r4 r5 b7", "BEGIN", "f(3,4,5)r"] {
+            execute_input(&mut game, &mut journal, input).unwrap();
+        }
+        assert_eq!(game.board.len(), 2);
+        assert_eq!(journal.len(), 2);
+
+        execute_input(&mut game, &mut journal, " Rollback ").unwrap();
+        assert_eq!(game.board.len(), 1);
+        assert_eq!(journal.len(), 1);
+
+        // b7 alone is not a valid set, the commit fails and rolls the turn back
+        for input in ["begin", "f(7)b"] {
+            execute_input(&mut game, &mut journal, input).unwrap();
+        }
+        assert!(matches!(
+            execute_input(&mut game, &mut journal, "commit"),
+            Err(TileCommandError::Turn(_))
+        ));
+        assert_eq!(game.board[0].len(), 4);
+        assert_eq!(journal.len(), 1);
+        assert_eq!(journal.replay_to(journal.len()).unwrap().board, game.board);
+
+        assert!(execute_input(&mut game, &mut journal, "commit").is_err());
+    }
+}
@@ -0,0 +1,380 @@
+use super::json::JsonError;
+use super::player::PlayerCommand;
+use super::tile_command::TileCommandError;
+use super::{Game, GameOperation};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub enum JournalError {
+    Io(String),
+    Json(JsonError),
+    /// The entry at `index` does not fit the game the entries before it built.
+    Replay {
+        index: usize,
+        error: TileCommandError,
+    },
+}
+
+impl Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::Io(msg) => write!(f, "Journal file error: {}", msg),
+            JournalError::Json(e) => write!(f, "Journal {}", e),
+            JournalError::Replay { index, error } => {
+                write!(
+                    f,
+                    "Journal entry {} could not be replayed: {}",
+                    index, error
+                )
+            }
+        }
+    }
+}
+
+impl Error for JournalError {}
+
+impl From<JsonError> for JournalError {
+    fn from(e: JsonError) -> Self {
+        JournalError::Json(e)
+    }
+}
+
+impl From<std::io::Error> for JournalError {
+    fn from(e: std::io::Error) -> Self {
+        JournalError::Io(e.to_string())
+    }
+}
+
+/// One line the user entered, together with the operations it produced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub input: String,
+    pub operations: Vec<GameOperation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub player_commands: Vec<PlayerCommand>,
+}
+
+/// The ordered record of every command applied to a `Game` since `Game::new`.
+/// Saved as JSON lines, one `JournalEntry` per line.
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    pub entries: Vec<JournalEntry>,
+    // the length of the journal when the open turn began
+    turn_start: usize,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Journal {
+            entries: Vec::new(),
+            turn_start: 0,
+        }
+    }
+
+    pub fn record(&mut self, input: &str, operations: Vec<GameOperation>) {
+        self.entries.push(JournalEntry {
+            input: input.to_string(),
+            operations,
+            player_commands: Vec::new(),
+        });
+    }
+
+    pub fn record_player(&mut self, input: &str, command: PlayerCommand) {
+        self.entries.push(JournalEntry {
+            input: input.to_string(),
+            operations: Vec::new(),
+            player_commands: vec![command],
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drop every entry after the first `len`, e.g. when a turn is rolled back.
+    pub fn truncate(&mut self, len: usize) {
+        self.entries.truncate(len);
+    }
+
+    /// Remember where a turn begins, so `rollback_turn` can drop the entries it recorded.
+    pub fn begin_turn(&mut self) {
+        self.turn_start = self.entries.len();
+    }
+
+    pub fn rollback_turn(&mut self) {
+        self.truncate(self.turn_start);
+    }
+
+    /// The length of the journal when the last turn began.
+    pub fn turn_start(&self) -> usize {
+        self.turn_start
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn to_json_lines(&self) -> Result<String, JsonError> {
+        let mut output = String::new();
+
+        for entry in &self.entries {
+            output.push_str(&serde_json::to_string(entry)?);
+            output.push('\n');
+        }
+
+        Ok(output)
+    }
+
+    pub fn from_json_lines(input: &str) -> Result<Self, JournalError> {
+        let mut entries = Vec::new();
+
+        for (i, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = serde_json::from_str::<JournalEntry>(line)
+                .map_err(|e| JsonError::Syntax(format!("line {}: {}", i + 1, e)))?;
+
+            entries.push(entry);
+        }
+
+        Ok(Journal {
+            entries,
+            turn_start: 0,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), JournalError> {
+        fs::write(path, self.to_json_lines()?)?;
+
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, JournalError> {
+        let input = fs::read_to_string(path)?;
+
+        Self::from_json_lines(&input)
+    }
+
+    /// Step through the journal, yielding the `Game` after each entry, or the error of the
+    /// first entry that does not apply, after which the replay stops.
+    pub fn replay(&self) -> Replay<'_> {
+        Replay {
+            entries: &self.entries,
+            step: 0,
+            game: Game::new(),
+        }
+    }
+
+    /// Rebuild the `Game` as it was after the first `step` entries.
+    pub fn replay_to(&self, step: usize) -> Result<Game, JournalError> {
+        let mut replay = self.replay();
+
+        while replay.step() < step {
+            match replay.next() {
+                Some(result) => result?,
+                None => break,
+            };
+        }
+
+        Ok(replay.game)
+    }
+
+    /// Like `replay_to`, but with the turn begun at `begin_turn` still open, so the entries
+    /// after its start are staged again. `step` must not be before the turn start.
+    pub fn replay_turn_to(&self, step: usize) -> Result<Game, JournalError> {
+        let mut game = self.replay_to(self.turn_start)?;
+        game.begin_turn().map_err(|e| JournalError::Replay {
+            index: self.turn_start,
+            error: e.into(),
+        })?;
+
+        for (index, entry) in self
+            .entries
+            .iter()
+            .enumerate()
+            .take(step)
+            .skip(self.turn_start)
+        {
+            Replay::apply(&mut game, entry)
+                .map_err(|error| JournalError::Replay { index, error })?;
+        }
+
+        Ok(game)
+    }
+}
+
+pub struct Replay<'a> {
+    entries: &'a [JournalEntry],
+    step: usize,
+    game: Game,
+}
+
+impl Replay<'_> {
+    /// Number of entries applied so far.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The entry that the next call to `next` will apply.
+    pub fn peek(&self) -> Option<&JournalEntry> {
+        self.entries.get(self.step)
+    }
+}
+
+impl Replay<'_> {
+    fn apply(game: &mut Game, entry: &JournalEntry) -> Result<(), TileCommandError> {
+        for operation in entry.operations.clone() {
+            game.operate(operation)?;
+        }
+
+        for command in entry.player_commands.clone() {
+            game.apply_player_command(command)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for Replay<'_> {
+    type Item = Result<Game, JournalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.get(self.step)?;
+
+        // recorded entries always applied, but a journal file may have been edited since
+        let mut game = self.game.clone();
+
+        if let Err(error) = Self::apply(&mut game, entry) {
+            // stop at the bad entry, `game` stays as the entries before it left it
+            self.entries = &[];
+
+            return Some(Err(JournalError::Replay {
+                index: self.step,
+                error,
+            }));
+        }
+
+        self.game = game;
+        self.step += 1;
+
+        Some(Ok(self.game.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::parser::command_sequence_to_operations;
+    use crate::game::Command;
+
+    fn play(game: &mut Game, journal: &mut Journal, input: &str) {
+        let operations = command_sequence_to_operations(input, game).expect("should validate");
+
+        journal.record(input, operations.clone());
+
+        for operation in operations {
+            game.operate(operation).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut game = Game::new();
+        let mut journal = Journal::new();
+
+        play(&mut game, &mut journal, "a0(3,4)r");
+        play(&mut game, &mut journal, "p(9,10,11)h");
+        play(&mut game, &mut journal, "d(5)r");
+
+        assert_eq!(journal.len(), 3);
+        assert_eq!(journal.replay_to(3).unwrap().board, game.board);
+        assert_eq!(journal.replay_to(10).unwrap().board, game.board);
+        assert_eq!(journal.replay_to(0).unwrap().board, Game::new().board);
+
+        let after_put = journal.replay_to(2).unwrap();
+        assert_eq!(after_put.board.len(), 2);
+        assert_eq!(after_put.board[0].len(), 2);
+
+        let games = journal.replay().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[1].board, after_put.board);
+    }
+
+    #[test]
+    fn test_replay_error() {
+        let mut game = Game::new();
+        let mut journal = Journal::new();
+
+        play(&mut game, &mut journal, "a0(3,4)r");
+        play(&mut game, &mut journal, "p(9,10,11)h");
+
+        // an edited journal that moves tiles from a set that does not exist
+        let mut edited = journal.clone();
+        edited.entries[1].operations[0].command = Command::Move;
+        edited.entries[1].operations[0].index = 4;
+        edited.record("d(5)r", Vec::new());
+
+        let mut replay = edited.replay();
+        assert!(replay.next().unwrap().is_ok());
+        assert!(matches!(
+            replay.next(),
+            Some(Err(JournalError::Replay { index: 1, .. }))
+        ));
+        assert!(replay.next().is_none());
+        assert_eq!(replay.step(), 1);
+        assert_eq!(replay.game().board, journal.replay_to(1).unwrap().board);
+
+        assert!(matches!(
+            edited.replay_to(3),
+            Err(JournalError::Replay { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut game = Game::new();
+        let mut journal = Journal::new();
+
+        play(&mut game, &mut journal, "a0(1,w)b");
+        play(&mut game, &mut journal, "p(r,b,h)10");
+
+        let join = PlayerCommand::Join {
+            name: "Alice".to_string(),
+            tile_count: 14,
+        };
+        game.apply_player_command(join.clone()).unwrap();
+        journal.record_player("join Alice", join);
+
+        let path = std::env::temp_dir().join(format!("rummy-journal-{}.jsonl", std::process::id()));
+        journal.save(&path).unwrap();
+        let loaded = Journal::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.entries[1].input, "p(r,b,h)10");
+        let replayed = loaded.replay_to(loaded.len()).unwrap();
+        assert_eq!(replayed.board, game.board);
+        assert_eq!(replayed.players, game.players);
+    }
+
+    #[test]
+    fn test_invalid_line() {
+        let result =
+            Journal::from_json_lines("{\"input\":\"a0(3)r\",\"operations\":[]}\nnot json\n");
+
+        assert!(result.unwrap_err().to_string().contains("line 2"));
+    }
+}
@@ -0,0 +1,159 @@
+use super::{tile::Tile, Game};
+use std::error::Error;
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone)]
+pub enum JsonError {
+    Syntax(String),
+    EmptyBoard,
+    InvalidTurn(usize),
+    InvalidTile { row: usize, col: usize, tile: Tile },
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Syntax(msg) => write!(f, "Invalid JSON: {}", msg),
+            JsonError::EmptyBoard => write!(f, "Invalid board, row 0 (the hand) is required!"),
+            JsonError::InvalidTurn(turn) => {
+                write!(f, "Invalid current_turn {}, no such player!", turn)
+            }
+            JsonError::InvalidTile { row, col, tile } => write!(
+                f,
+                "Invalid tile {} at row {}, position {}, number must be 1-13!",
+                tile, row, col
+            ),
+        }
+    }
+}
+
+impl Error for JsonError {}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(e: serde_json::Error) -> Self {
+        JsonError::Syntax(e.to_string())
+    }
+}
+
+impl Game {
+    pub fn to_json(&self) -> Result<String, JsonError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(input: &str) -> Result<Self, JsonError> {
+        let game: Game = serde_json::from_str(input)?;
+        validate_board(&game.board)?;
+
+        if game.current_turn >= game.players.len() {
+            return Err(JsonError::InvalidTurn(game.current_turn));
+        }
+
+        Ok(game)
+    }
+}
+
+pub(crate) fn validate_board(board: &[Vec<Tile>]) -> Result<(), JsonError> {
+    if board.is_empty() {
+        return Err(JsonError::EmptyBoard);
+    }
+
+    validate_sets(board)
+}
+
+pub(crate) fn validate_sets(sets: &[Vec<Tile>]) -> Result<(), JsonError> {
+    for (row, tiles) in sets.iter().enumerate() {
+        for (col, tile) in tiles.iter().enumerate() {
+            // a wildcard without an assigned value is stored with number 251, a pinned one needs a value
+            let is_valid =
+                (1..=13).contains(&tile.number) || (tile.is_free_wildcard() && tile.number == 251);
+
+            if !is_valid {
+                return Err(JsonError::InvalidTile {
+                    row,
+                    col,
+                    tile: tile.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tile_color::TileColor;
+
+    #[test]
+    fn test_tile_json() {
+        let tile = Tile::new(7, TileColor::Orange, true);
+        let json = serde_json::to_string(&tile).unwrap();
+
+        assert_eq!(json, r#"{"number":7,"color":"orange","is_wildcard":true}"#);
+        assert_eq!(serde_json::from_str::<Tile>(&json).unwrap(), tile);
+
+        let pinned = Tile::new_pinned(7, TileColor::Orange);
+        let json = serde_json::to_string(&pinned).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"number":7,"color":"orange","is_wildcard":true,"is_pinned":true}"#
+        );
+        assert_eq!(serde_json::from_str::<Tile>(&json).unwrap(), pinned);
+    }
+
+    #[test]
+    fn test_game_round_trip() {
+        let game = Game::new_with_board(vec![
+            vec![
+                Tile::new(3, TileColor::Red, false),
+                Tile::new(251, TileColor::Red, true),
+            ],
+            vec![
+                Tile::new(10, TileColor::Black, false),
+                Tile::new(10, TileColor::Blue, true),
+                Tile::new(10, TileColor::Red, false),
+            ],
+        ]);
+
+        let json = game.to_json().unwrap();
+        let parsed = Game::from_json(&json).unwrap();
+
+        assert_eq!(parsed.board, game.board);
+        assert_eq!(parsed.players, game.players);
+
+        let legacy = Game::from_json(r#"{"board":[[]]}"#).unwrap();
+        assert_eq!(legacy.players.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_board() {
+        assert!(matches!(
+            Game::from_json(r#"{"board":[]}"#),
+            Err(JsonError::EmptyBoard)
+        ));
+
+        assert!(matches!(
+            Game::from_json(r#"{"board":[[]],"players":[],"current_turn":0}"#),
+            Err(JsonError::InvalidTurn(0))
+        ));
+
+        assert!(matches!(
+            Game::from_json(r#"{"board":[[{"number":14,"color":"red","is_wildcard":false}]]}"#),
+            Err(JsonError::InvalidTile { row: 0, col: 0, .. })
+        ));
+
+        assert!(matches!(
+            Game::from_json(
+                r#"{"board":[[{"number":251,"color":"red","is_wildcard":true,"is_pinned":true}]]}"#
+            ),
+            Err(JsonError::InvalidTile { row: 0, col: 0, .. })
+        ));
+
+        assert!(matches!(
+            Game::from_json(r#"{"board":[[{"number":3,"color":"green","is_wildcard":false}]]}"#),
+            Err(JsonError::Syntax(_))
+        ));
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Word(String),
+    Number(String),
+    LParen,
+    RParen,
+    Comma,
+    Greater,
+    At,
+    Semicolon,
+    Dash,
+    DotDot,
+    Equals,
+    Colon,
+    Tilde,
+    Unknown(char),
+    End,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Word(word) => write!(f, "'{}'", word),
+            TokenKind::Number(number) => write!(f, "'{}'", number),
+            TokenKind::LParen => write!(f, "'('"),
+            TokenKind::RParen => write!(f, "')'"),
+            TokenKind::Comma => write!(f, "','"),
+            TokenKind::Greater => write!(f, "'>'"),
+            TokenKind::At => write!(f, "'@'"),
+            TokenKind::Semicolon => write!(f, "';'"),
+            TokenKind::Dash => write!(f, "'-'"),
+            TokenKind::DotDot => write!(f, "'..'"),
+            TokenKind::Equals => write!(f, "'='"),
+            TokenKind::Colon => write!(f, "':'"),
+            TokenKind::Tilde => write!(f, "'~'"),
+            TokenKind::Unknown(ch) => write!(f, "'{}'", ch),
+            TokenKind::End => write!(f, "end of input"),
+        }
+    }
+}
+
+impl TokenKind {
+    /// Number of characters the token spans in the input.
+    pub fn width(&self) -> usize {
+        match self {
+            TokenKind::Word(text) | TokenKind::Number(text) => text.chars().count(),
+            TokenKind::End => 0,
+            TokenKind::DotDot => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// A token and the column (0-based, in characters) it starts at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub column: usize,
+}
+
+/// Split the input into tokens, whitespace only separates tokens and words are
+/// lowercased. The last token is always `TokenKind::End`.
+pub(crate) fn tokenize(input: &str) -> Vec<Token> {
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        let column = i;
+
+        if ch.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let kind = if ch.is_ascii_alphabetic() || ch.is_ascii_digit() {
+            let is_word = ch.is_ascii_alphabetic();
+            let mut text = String::new();
+
+            while i < chars.len()
+                && (is_word && chars[i].is_ascii_alphabetic()
+                    || !is_word && chars[i].is_ascii_digit())
+            {
+                text.push(chars[i].to_ascii_lowercase());
+                i += 1;
+            }
+
+            if is_word {
+                TokenKind::Word(text)
+            } else {
+                TokenKind::Number(text)
+            }
+        } else if ch == '.' && chars.get(i + 1) == Some(&'.') {
+            i += 2;
+            TokenKind::DotDot
+        } else {
+            i += 1;
+
+            match ch {
+                '(' => TokenKind::LParen,
+                ')' => TokenKind::RParen,
+                ',' => TokenKind::Comma,
+                '>' => TokenKind::Greater,
+                '@' => TokenKind::At,
+                ';' => TokenKind::Semicolon,
+                '-' => TokenKind::Dash,
+                '=' => TokenKind::Equals,
+                ':' => TokenKind::Colon,
+                '~' => TokenKind::Tilde,
+                _ => TokenKind::Unknown(ch),
+            }
+        };
+
+        tokens.push(Token { kind, column });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::End,
+        column: chars.len(),
+    });
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let kinds = tokenize("r2(11)h(r,o)2; m3>5 s1@2")
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        let word = |s: &str| TokenKind::Word(s.to_string());
+        let number = |s: &str| TokenKind::Number(s.to_string());
+
+        assert_eq!(
+            kinds,
+            vec![
+                word("r"),
+                number("2"),
+                TokenKind::LParen,
+                number("11"),
+                TokenKind::RParen,
+                word("h"),
+                TokenKind::LParen,
+                word("r"),
+                TokenKind::Comma,
+                word("o"),
+                TokenKind::RParen,
+                number("2"),
+                TokenKind::Semicolon,
+                word("m"),
+                number("3"),
+                TokenKind::Greater,
+                number("5"),
+                word("s"),
+                number("1"),
+                TokenKind::At,
+                number("2"),
+                TokenKind::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_case() {
+        let kinds = tokenize("A0 (3) RED")
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(kinds[0], TokenKind::Word("a".to_string()));
+        assert_eq!(kinds[5], TokenKind::Word("red".to_string()));
+    }
+
+    #[test]
+    fn test_tokenize_ranges() {
+        let kinds = tokenize("(3-5,10..13)")
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(kinds[2], TokenKind::Dash);
+        assert_eq!(kinds[6], TokenKind::DotDot);
+        assert_eq!(tokenize("3.4")[1].kind, TokenKind::Unknown('.'));
+    }
+
+    #[test]
+    fn test_token_columns() {
+        let tokens = tokenize("a0( 3,x!)");
+        let columns = tokens.iter().map(|t| t.column).collect::<Vec<_>>();
+
+        assert_eq!(columns, vec![0, 1, 2, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(tokens[6].kind, TokenKind::Unknown('!'));
+    }
+}
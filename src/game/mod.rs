@@ -1,31 +1,101 @@
-pub(crate) mod parser;
-pub(crate) mod tile;
-pub(crate) mod tile_color;
-pub(crate) mod tile_command;
-
+pub mod input;
+pub mod journal;
+pub mod json;
+pub(crate) mod lexer;
+pub mod notation;
+pub mod parser;
+pub mod player;
+pub mod tile;
+pub mod tile_color;
+pub mod tile_command;
+pub mod turn;
+
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{self, Display};
 use std::vec;
 
+use player::Player;
 use tile::Tile;
 
 use crate::game::tile_color::TileColor;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// The kind of change a `GameOperation` makes.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Command {
     Add,
     Put,
     Draw,
     Replace,
+    Play,
+    Move,
+    Remove,
+    Delete,
+    Split,
+    Merge,
 }
 
-#[derive(Debug)]
+/// One change to the board, produced from a validated `TileCommand` and applied with `Game::operate`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameOperation {
     pub command: Command,
     pub index: usize,
     pub replace_tiles: Option<Vec<Tile>>,
     pub tiles: Vec<Tile>,
+    #[serde(default)]
+    pub target: Option<usize>,
+    #[serde(default)]
+    pub position: Option<usize>,
+}
+
+/// An operation that does not fit the board, e.g. one loaded from an edited journal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OperationError {
+    InvalidIndex(usize),
+    NotInHand,
+    NoArrangement,
+    OnlyWildcards,
+    MissingReplaceTiles,
+    WildcardCount {
+        index: usize,
+        wildcards: usize,
+        replacements: usize,
+    },
+}
+
+impl Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperationError::InvalidIndex(index) => write!(f, "There is no set at index {}!", index),
+            OperationError::NotInHand => write!(f, "The tiles are not in the hand!"),
+            OperationError::NoArrangement => {
+                write!(f, "The tiles and wildcards do not form a valid set!")
+            }
+            OperationError::OnlyWildcards => {
+                write!(
+                    f,
+                    "A set of wildcards only needs a tile to guess their values from!"
+                )
+            }
+            OperationError::MissingReplaceTiles => {
+                write!(f, "Replace needs the tiles that take the wildcards' place!")
+            }
+            OperationError::WildcardCount {
+                index,
+                wildcards,
+                replacements,
+            } => write!(
+                f,
+                "The set at index {} holds {} wildcard(s), but {} replacement tile(s) were given!",
+                index, wildcards, replacements
+            ),
+        }
+    }
 }
 
+impl Error for OperationError {}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum TilesType {
     PureColor,
@@ -44,28 +114,80 @@ impl GameOperation {
             index,
             replace_tiles,
             tiles,
+            target: None,
+            position: None,
+        }
+    }
+
+    pub fn new_move(index: usize, target: usize, tiles: Vec<Tile>) -> Self {
+        GameOperation {
+            command: Command::Move,
+            index,
+            replace_tiles: None,
+            tiles,
+            target: Some(target),
+            position: None,
         }
     }
-}
 
-pub trait ToTiles {
-    fn to_tiles(&self) -> GameOperation;
+    pub fn new_merge(index: usize, target: usize) -> Self {
+        GameOperation {
+            command: Command::Merge,
+            index,
+            replace_tiles: None,
+            tiles: Vec::new(),
+            target: Some(target),
+            position: None,
+        }
+    }
+
+    pub fn new_split(index: usize, position: usize) -> Self {
+        GameOperation {
+            command: Command::Split,
+            index,
+            replace_tiles: None,
+            tiles: Vec::new(),
+            target: None,
+            position: Some(position),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+/// The board and the players, `board[0]` is the hand and every other row a set on the table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Game {
     pub board: Vec<Vec<Tile>>,
+    #[serde(default = "Player::default_players")]
+    pub players: Vec<Player>,
+    #[serde(default)]
+    pub current_turn: usize,
+    #[serde(skip)]
+    turn_snapshot: Option<Box<Game>>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Self {
         Game {
             board: vec![vec![]],
+            players: Player::default_players(),
+            current_turn: 0,
+            turn_snapshot: None,
         }
     }
 
     pub fn new_with_board(board: Vec<Vec<Tile>>) -> Self {
-        Game { board }
+        Game {
+            board,
+            players: Player::default_players(),
+            current_turn: 0,
+            turn_snapshot: None,
+        }
     }
 
     pub fn validate_index(&self, idx: usize) -> bool {
@@ -76,64 +198,196 @@ impl Game {
         self.board.clone()
     }
 
-    pub(crate) fn operate(&mut self, operation: GameOperation) {
+    /// Check that every tile is in the row, any joker in the row matches a joker.
+    pub fn row_contains(&self, index: usize, tiles: &[Tile]) -> bool {
+        let mut row = self.board[index].clone();
+
+        tiles.iter().all(|tile| {
+            let position = row.iter().position(|t| t.is_same_tile(tile));
+            position.map(|i| row.swap_remove(i)).is_some()
+        })
+    }
+
+    pub fn hand_contains(&self, tiles: &[Tile]) -> bool {
+        self.row_contains(0, tiles)
+    }
+
+    fn take_from_row(&mut self, index: usize, tiles: &[Tile]) -> Vec<Tile> {
+        let mut taken = Vec::new();
+
+        for tile in tiles {
+            if let Some(i) = self.board[index].iter().position(|t| t.is_same_tile(tile)) {
+                taken.push(self.board[index].remove(i));
+            }
+        }
+
+        taken
+    }
+
+    fn add_to_row(&mut self, index: usize, tiles: Vec<Tile>) {
+        self.board[index].extend(tiles);
+        self.board[index].sort_unstable();
+
+        if index != 0 {
+            let tiles = self.board[index].clone();
+            // a row the wildcards cannot be guessed for is kept as it is
+            if let Ok(tiles) = self.wildcard_to_tiles(tiles) {
+                self.board[index] = tiles[0].clone();
+
+                if tiles.len() > 1 {
+                    self.board.push(tiles[1].clone());
+                }
+            }
+        }
+    }
+
+    // split a run that lost a tile from the middle, e.g. 5,6,8,9 becomes 5,6 and 8,9
+    fn split_run_gaps(&mut self, index: usize) {
+        let row = &self.board[index];
+
+        if index == 0
+            || row.is_empty()
+            || Self::wildcard_count(row) > 0
+            || Self::get_tiles_type(row) != TilesType::PureColor
+        {
+            return;
+        }
+
+        let mut runs: Vec<Vec<Tile>> = Vec::new();
+
+        for tile in row {
+            match runs.last_mut() {
+                Some(run) if run[run.len() - 1].number + 1 == tile.number => run.push(tile.clone()),
+                _ => runs.push(vec![tile.clone()]),
+            }
+        }
+
+        let mut runs = runs.into_iter();
+
+        if let Some(first) = runs.next() {
+            self.board[index] = first;
+        }
+
+        self.board.extend(runs);
+    }
+
+    /// Apply one operation, the board is left unchanged if it returns an error.
+    pub fn operate(&mut self, operation: GameOperation) -> Result<(), OperationError> {
+        let index = operation.index;
+
         match operation.command {
-            Command::Put => {
-                let mut tiles = operation.tiles;
+            Command::Play if !self.hand_contains(&operation.tiles) => {
+                return Err(OperationError::NotInHand);
+            }
+            Command::Put | Command::Play => {}
+            Command::Move | Command::Merge => {
+                self.check_index(index)?;
+                self.check_index(operation.target.unwrap_or(index))?;
+            }
+            _ => self.check_index(index)?,
+        }
+
+        match operation.command {
+            Command::Put | Command::Play => {
+                let mut tiles = operation.tiles.clone();
                 tiles.sort_unstable();
 
-                let tiles = self.wildcard_to_tiles(tiles).clone();
+                // arrange before taking from the hand, so a failed play loses no tiles
+                let tiles_set = self.wildcard_to_tiles(tiles)?;
 
-                for tile in tiles {
-                    self.board.push(tile);
+                if operation.command == Command::Play {
+                    self.take_from_row(0, &operation.tiles);
+                }
+
+                for tiles in tiles_set {
+                    self.board.push(tiles);
                 }
             }
 
             Command::Replace => {
-                while self.board.len() <= operation.index {
-                    self.board.push(Vec::new());
-                }
-                let wildcard_count = Self::wildcard_count(&self.board[operation.index]);
-                let replace_tiles = operation.replace_tiles.unwrap();
+                let wildcard_count = Self::wildcard_count(&self.board[index]);
+                let replace_tiles = operation
+                    .replace_tiles
+                    .ok_or(OperationError::MissingReplaceTiles)?;
 
                 if wildcard_count != replace_tiles.len() {
-                    return;
+                    return Err(OperationError::WildcardCount {
+                        index,
+                        wildcards: wildcard_count,
+                        replacements: replace_tiles.len(),
+                    });
                 }
 
-                let tiles_to_replace = self.board[operation.index].clone();
-                self.board[operation.index] =
-                    self.replace_wildcards(tiles_to_replace, replace_tiles);
-
                 let mut tiles = operation.tiles;
                 tiles.extend(vec![Tile::new(251, TileColor::Red, true); wildcard_count]);
 
-                let tiles_set = self.wildcard_to_tiles(tiles);
+                let tiles_set = self.wildcard_to_tiles(tiles)?;
+
+                let tiles_to_replace = self.board[index].clone();
+                self.board[index] = self.replace_wildcards(tiles_to_replace, replace_tiles);
 
                 for tiles in tiles_set {
                     self.board.push(tiles);
                 }
             }
 
-            _ => {
-                while self.board.len() <= operation.index {
-                    self.board.push(Vec::new());
-                }
-                self.board[operation.index].extend(operation.tiles);
-                self.board[operation.index].sort_unstable();
+            Command::Move => {
+                let target = operation.target.unwrap_or(index);
+                let tiles = self.take_from_row(index, &operation.tiles);
+
+                self.add_to_row(target, tiles);
+                self.split_run_gaps(index);
+            }
 
-                if operation.index != 0 {
-                    let tiles = self.board[operation.index].clone();
-                    let tiles = self.wildcard_to_tiles(tiles);
+            Command::Remove => {
+                self.take_from_row(index, &operation.tiles);
+                self.split_run_gaps(index);
+                self.trim_empty_rows();
+            }
 
-                    if !tiles.is_empty() {
-                        self.board[operation.index] = tiles[0].clone();
+            Command::Delete => {
+                self.board[index].clear();
+                self.trim_empty_rows();
+            }
 
-                        if tiles.len() > 1 {
-                            self.board.push(tiles[1].clone());
-                        }
-                    }
+            Command::Split => {
+                let position = operation.position.unwrap_or(0);
+                let row = &mut self.board[index];
+
+                if position > 0 && position < row.len() {
+                    let second = row.split_off(position);
+                    self.board.push(second);
                 }
             }
+
+            Command::Merge => {
+                let target = operation.target.unwrap_or(index);
+                let tiles = std::mem::take(&mut self.board[index]);
+
+                self.board[target].extend(tiles);
+                self.board[target].sort_unstable();
+                self.trim_empty_rows();
+            }
+
+            Command::Add | Command::Draw => {
+                self.add_to_row(index, operation.tiles);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), OperationError> {
+        self.validate_index(index)
+            .then_some(())
+            .ok_or(OperationError::InvalidIndex(index))
+    }
+
+    // emptied sets stay in place so the indices of later sets do not shift,
+    // only the empty rows at the end of the board are dropped
+    fn trim_empty_rows(&mut self) {
+        while self.board.len() > 1 && self.board[self.board.len() - 1].is_empty() {
+            self.board.pop();
         }
     }
 
@@ -180,12 +434,10 @@ impl Game {
 
     // assume user provide tiles are correct, all tiles are provide in same number
     fn split_mixed_colors_tiles(&self, tiles: Vec<Tile>) -> Vec<Vec<Tile>> {
-        let n = tiles.len();
         let mut colors_map = HashMap::new();
         let mut numbers_map = HashMap::new();
 
-        for i in 0..n {
-            let tile = &tiles[i];
+        for (i, tile) in tiles.iter().enumerate() {
             let color = tile.color;
             let number = tile.number;
 
@@ -246,38 +498,51 @@ impl Game {
 
     pub fn reset(&mut self) {
         self.board = vec![vec![]];
+        self.players = Player::default_players();
+        self.current_turn = 0;
+        self.turn_snapshot = None;
     }
 
-    pub fn wildcard_count(tiles: &Vec<Tile>) -> usize {
-        tiles
-            .iter()
-            .filter(|t| t.is_wildcard)
-            .collect::<Vec<_>>()
-            .len()
+    pub fn wildcard_count(tiles: &[Tile]) -> usize {
+        tiles.iter().filter(|t| t.is_wildcard).count()
+    }
+
+    // pinned jokers keep their value, only the free ones are guessed
+    fn wildcard_to_tiles(&mut self, tiles: Vec<Tile>) -> Result<Vec<Vec<Tile>>, OperationError> {
+        let tiles_set = self.guess_wildcards(tiles)?;
+
+        if tiles_set.iter().all(|tiles| tiles.is_empty()) {
+            return Err(OperationError::NoArrangement);
+        }
+
+        Ok(tiles_set)
     }
 
-    fn wildcard_to_tiles(&mut self, tiles: Vec<Tile>) -> Vec<Vec<Tile>> {
-        let wildcard_count = Self::wildcard_count(&tiles);
+    fn guess_wildcards(&self, tiles: Vec<Tile>) -> Result<Vec<Vec<Tile>>, OperationError> {
+        let wildcard_count = tiles.iter().filter(|t| t.is_free_wildcard()).count();
 
         if wildcard_count == 0 {
-            return self.check_and_split(tiles);
+            return Ok(self.check_and_split(tiles));
         }
 
         let tiles = tiles
-            .clone()
             .into_iter()
-            .filter(|tile| !tile.is_wildcard)
+            .filter(|tile| !tile.is_free_wildcard())
             .collect::<Vec<_>>();
+        let first = tiles.first().ok_or(OperationError::OnlyWildcards)?.clone();
         let tiles_type = Self::get_tiles_type(&tiles);
 
-        match tiles_type {
+        let tiles_set = match tiles_type {
             TilesType::PureColor => {
-                let color = tiles[0].color;
+                let color = first.color;
 
-                let low = tiles.iter().min_by_key(|tile| tile.number).unwrap().number;
-                let high = tiles.iter().max_by_key(|tile| tile.number).unwrap().number;
+                let (low, high) = tiles
+                    .iter()
+                    .fold((first.number, first.number), |(low, high), tile| {
+                        (low.min(tile.number), high.max(tile.number))
+                    });
 
-                let low = 1.max(low - 1);
+                let low = 1.max(low.saturating_sub(1));
                 let high = 13.min(high + 1);
 
                 let mut tiles_set = Vec::new();
@@ -320,7 +585,7 @@ impl Game {
             }
 
             TilesType::MixedColor => {
-                let number = tiles[0].number;
+                let number = first.number;
 
                 let mut tiles_set = Vec::new();
 
@@ -362,16 +627,16 @@ impl Game {
 
                 tiles_set
             }
-        }
+        };
+
+        Ok(tiles_set)
     }
 
     fn replace_wildcards(&self, tiles: Vec<Tile>, replace_tiles: Vec<Tile>) -> Vec<Tile> {
         let mut tiles = tiles;
 
         for replace_tile in replace_tiles {
-            for i in 0..tiles.len() {
-                let tile = &mut tiles[i];
-
+            for tile in tiles.iter_mut() {
                 if tile.is_wildcard {
                     tile.is_wildcard = false;
                     tile.number = replace_tile.number;
@@ -487,6 +752,80 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_pinned_wildcard() {
+        let mut game = Game::new();
+        let run = |joker: Tile| {
+            vec![
+                Tile::new(5, TileColor::Red, false),
+                Tile::new(6, TileColor::Red, false),
+                joker,
+            ]
+        };
+
+        game.operate(GameOperation::new(
+            Command::Put,
+            usize::MAX,
+            run(Tile::new(251, TileColor::Red, true)),
+            None,
+        ))
+        .unwrap();
+        game.operate(GameOperation::new(
+            Command::Put,
+            usize::MAX,
+            run(Tile::new_pinned(7, TileColor::Red)),
+            None,
+        ))
+        .unwrap();
+
+        // the free joker is guessed as 4, the pinned one stays 7
+        assert_eq!(game.board[1][0], Tile::new(4, TileColor::Red, true));
+        assert_eq!(game.board[2][2], Tile::new_pinned(7, TileColor::Red));
+    }
+
+    #[test]
+    fn test_failed_play() {
+        let mut game = Game::new();
+        let hand = vec![
+            Tile::new(3, TileColor::Red, false),
+            Tile::new(9, TileColor::Red, false),
+            Tile::new(251, TileColor::Red, true),
+        ];
+        game.operate(GameOperation::new(Command::Add, 0, hand, None))
+            .unwrap();
+        let board = game.get_board();
+
+        // no joker fills the gap between 3 and 9, the hand keeps its tiles
+        let result = game.operate(GameOperation::new(
+            Command::Play,
+            usize::MAX,
+            board[0].clone(),
+            None,
+        ));
+        assert_eq!(result, Err(OperationError::NoArrangement));
+        assert_eq!(game.board, board);
+
+        let result = game.operate(GameOperation::new(
+            Command::Play,
+            usize::MAX,
+            vec![Tile::new(4, TileColor::Red, false)],
+            None,
+        ));
+        assert_eq!(result, Err(OperationError::NotInHand));
+        assert_eq!(game.board, board);
+
+        // nothing to guess the jokers from
+        let joker = Tile::new(251, TileColor::Red, true);
+        let result = game.operate(GameOperation::new(
+            Command::Put,
+            usize::MAX,
+            vec![joker.clone(), joker.clone(), joker],
+            None,
+        ));
+        assert_eq!(result, Err(OperationError::OnlyWildcards));
+        assert_eq!(game.board, board);
+    }
+
     #[test]
     fn test3() {
         let mut game = Game::new();
@@ -495,7 +834,8 @@ mod tests {
             0,
             vec![Tile::new(10, TileColor::Red, false)],
             None,
-        ));
+        ))
+        .unwrap();
 
         let tiles1 = vec![
             Tile::new(10, TileColor::Black, false),
@@ -516,18 +856,23 @@ mod tests {
 
         let replace_tiles = vec![Tile::new(11, TileColor::Black, false)];
 
-        game.operate(GameOperation::new(Command::Put, usize::MAX, tiles1, None));
+        game.operate(GameOperation::new(Command::Put, usize::MAX, tiles1, None))
+            .unwrap();
 
-        game.operate(GameOperation::new(Command::Add, 1, tiles2, None));
+        game.operate(GameOperation::new(Command::Add, 1, tiles2, None))
+            .unwrap();
 
-        game.operate(GameOperation::new(
+        // there is no set at index 2, the board is left as it was
+        let board = game.get_board();
+        let result = game.operate(GameOperation::new(
             Command::Replace,
             2,
             tiles3,
             Some(replace_tiles),
         ));
 
-        let board = game.get_board();
+        assert_eq!(result, Err(OperationError::InvalidIndex(2)));
+        assert_eq!(game.board, board);
 
         println!("{:?}", board);
     }
@@ -0,0 +1,271 @@
+use super::parser::{parse_set, parse_sets, parse_tile, CommandAst, ParseError};
+use super::tile_command::TileCommandError;
+use super::{tile::Tile, Game};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    Syntax(Vec<ParseError>),
+    /// A board error, `line` is 1-based.
+    Line {
+        line: usize,
+        errors: Vec<ParseError>,
+    },
+    Tiles(TileCommandError),
+}
+
+impl Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |errors: &[ParseError]| {
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        match self {
+            NotationError::Syntax(errors) => write!(f, "{}", join(errors)),
+            NotationError::Line { line, errors } => write!(f, "Line {}: {}", line, join(errors)),
+            NotationError::Tiles(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for NotationError {}
+
+impl From<TileCommandError> for NotationError {
+    fn from(e: TileCommandError) -> Self {
+        NotationError::Tiles(e)
+    }
+}
+
+/// One set of tiles, written as its tiles separated by spaces, e.g. `r3 r4 r(w5)`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TileSet(pub Vec<Tile>);
+
+impl Display for TileSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tiles = self
+            .0
+            .iter()
+            .map(|tile| tile.to_string())
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", tiles.join(" "))
+    }
+}
+
+impl FromStr for TileSet {
+    type Err = NotationError;
+
+    /// Accepts anything the initial hand accepts, such as `red 3 r:4,5 w`, and an empty set.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let commands = parse_set(input).map_err(NotationError::Syntax)?;
+
+        Ok(TileSet(commands_to_tiles(&commands)?))
+    }
+}
+
+impl FromStr for Tile {
+    type Err = NotationError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let command = parse_tile(input).map_err(|e| NotationError::Syntax(vec![e]))?;
+
+        command
+            .to_tiles()?
+            .into_iter()
+            .next()
+            .ok_or(NotationError::Tiles(TileCommandError::InvalidArgs))
+    }
+}
+
+/// The board as its sets separated by `; `, starting with the hand, e.g. `r3 w; b10 h10 o10`.
+/// Emptied sets are kept so the indices of the others stay the same.
+impl Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sets = self
+            .board
+            .iter()
+            .map(|tiles| TileSet(tiles.clone()).to_string())
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", sets.join("; "))
+    }
+}
+
+impl FromStr for Game {
+    type Err = NotationError;
+
+    /// Reads a board written by `Display`, sets may also be separated by line breaks.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut board = Vec::new();
+
+        for (i, line) in input.lines().enumerate() {
+            let sets = parse_sets(line).map_err(|errors| NotationError::Line {
+                line: i + 1,
+                errors,
+            })?;
+
+            for commands in sets {
+                board.push(commands_to_tiles(&commands)?);
+            }
+        }
+
+        if board.is_empty() {
+            board.push(Vec::new());
+        }
+
+        Ok(Game::new_with_board(board))
+    }
+}
+
+fn commands_to_tiles(commands: &[CommandAst]) -> Result<Vec<Tile>, TileCommandError> {
+    Ok(commands
+        .iter()
+        .map(CommandAst::to_tiles)
+        .collect::<Result<Vec<_>, _>>()?
+        .concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::parser::command_sequence_to_operations;
+    use crate::game::tile_color::TileColor;
+
+    fn all_tiles() -> Vec<Tile> {
+        let mut tiles = vec![Tile::new(251, TileColor::Red, true)];
+
+        for color in TileColor::iter() {
+            for number in 1..=13 {
+                tiles.push(Tile::new(number, color, false));
+                tiles.push(Tile::new_pinned(number, color));
+                tiles.push(Tile::new(number, color, true));
+            }
+        }
+
+        tiles
+    }
+
+    #[test]
+    fn test_tile_round_trip() {
+        for tile in all_tiles() {
+            let text = tile.to_string();
+            let parsed = text.parse::<Tile>().expect(&text);
+
+            assert_eq!(parsed, tile, "{}", text);
+            assert_eq!(parsed.to_string(), text);
+        }
+
+        assert_eq!(Tile::new(3, TileColor::Red, false).to_string(), "r3");
+        assert_eq!(Tile::new(251, TileColor::Red, true).to_string(), "w");
+        assert_eq!(Tile::new_pinned(7, TileColor::Black).to_string(), "h(w7)");
+        assert_eq!(Tile::new(6, TileColor::Blue, true).to_string(), "b(w~6)");
+    }
+
+    #[test]
+    fn test_tile_lenient() {
+        for (input, expected) in [
+            ("Red 3", "r3"),
+            ("r(3)", "r3"),
+            ("o(w)", "w"),
+            ("b w=12", "b(w12)"),
+            ("black:w1", "h(w1)"),
+            ("b w ~ 6", "b(w~6)"),
+        ] {
+            assert_eq!(input.parse::<Tile>().expect(input).to_string(), expected);
+        }
+
+        // the guessed value of a free joker is kept, but it is not pinned
+        assert!("b(w~6)".parse::<Tile>().unwrap().is_free_wildcard());
+    }
+
+    #[test]
+    fn test_tile_errors() {
+        for (input, column) in [("", 0), ("r3 r4", 3), ("r:3,4", 0), ("r14", 1), ("x", 0)] {
+            match input.parse::<Tile>() {
+                Err(NotationError::Syntax(errors)) => {
+                    assert_eq!(errors[0].column, column, "{}", input)
+                }
+                other => panic!("unexpected result {:?} for {}", other, input),
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_round_trip() {
+        let set = TileSet(all_tiles());
+        let text = set.to_string();
+
+        assert_eq!(text.parse::<TileSet>().unwrap(), set);
+        assert_eq!("".parse::<TileSet>().unwrap(), TileSet::default());
+        assert_eq!(
+            "r:3-5 w b w7".parse::<TileSet>().unwrap().to_string(),
+            "r3 r4 r5 w b(w7)"
+        );
+        assert!("r3; r4".parse::<TileSet>().is_err());
+    }
+
+    #[test]
+    fn test_board_round_trip() {
+        let board = vec![
+            vec![],
+            vec![
+                Tile::new(5, TileColor::Red, false),
+                Tile::new_pinned(6, TileColor::Red),
+                Tile::new(7, TileColor::Red, false),
+            ],
+            vec![],
+            vec![
+                Tile::new(10, TileColor::Blue, false),
+                Tile::new(10, TileColor::Black, false),
+                Tile::new(251, TileColor::Red, true),
+            ],
+        ];
+        let game = Game::new_with_board(board.clone());
+        let text = game.to_string();
+
+        assert_eq!(text, "; r5 r(w6) r7; ; b10 h10 w");
+        assert_eq!(text.parse::<Game>().unwrap().board, board);
+
+        // jokers placed by a play keep the value they were given
+        let mut game = Game::new_with_board(vec![vec![
+            Tile::new(3, TileColor::Red, false),
+            Tile::new(4, TileColor::Red, false),
+            Tile::new(251, TileColor::Red, true),
+        ]]);
+        let operations = command_sequence_to_operations("f(3,4,w)r", &game).unwrap();
+        game.operate(operations[0].clone()).unwrap();
+        let text = game.to_string();
+
+        assert!(game.board[1]
+            .iter()
+            .any(|t| t.is_free_wildcard() && t.number != 251));
+        assert!(text.contains("r(w~"), "{}", text);
+        assert_eq!(text.parse::<Game>().unwrap().board, game.board);
+
+        // one set per line works as well
+        let game = "r3 r4\nb1 b2 b3; o9\n".parse::<Game>().unwrap();
+        assert_eq!(game.to_string(), "r3 r4; b1 b2 b3; o9");
+
+        assert_eq!("".parse::<Game>().unwrap().board, vec![vec![]]);
+    }
+
+    #[test]
+    fn test_board_errors() {
+        let e = "r3\nb1 x2; b0".parse::<Game>().unwrap_err();
+        let columns = match &e {
+            NotationError::Line { line: 2, errors } => {
+                errors.iter().map(|e| e.column).collect::<Vec<_>>()
+            }
+            other => panic!("unexpected error {:?}", other),
+        };
+
+        assert_eq!(columns, vec![3, 8]);
+        assert!(e.to_string().starts_with("Line 2: Column 4:"));
+    }
+}
@@ -1,374 +1,1543 @@
+use super::lexer::{tokenize, Token, TokenKind};
+use super::player::PlayerCommand;
+use super::tile::Tile;
+use super::tile_color::TileColor;
 use super::tile_command::{TileCommand, TileCommandError};
-use crate::game::{Command, Game};
-use regex::Regex;
+use crate::game::{Command, Game, GameOperation};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::ops::Range;
 
-pub(crate) struct Parser {
-    pattern: Regex,
-    init_pattern: Regex,
+const PLAYER_COMMANDS: [&str; 6] = ["join", "next", "drew", "played", "took", "meld"];
+const TILE_COMMANDS: [(&str, Command); 9] = [
+    ("a", Command::Add),
+    ("p", Command::Put),
+    ("d", Command::Draw),
+    ("r", Command::Replace),
+    ("f", Command::Play),
+    ("m", Command::Move),
+    ("x", Command::Remove),
+    ("s", Command::Split),
+    ("j", Command::Merge),
+];
+const HAND_TILE: &str = "a tile such as r3, r(3), r:3,4,5 or w";
+
+/// A syntax error, `column` is the 0-based character position of the offending token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub column: usize,
+    pub expected: String,
+    pub found: String,
+}
+
+impl ParseError {
+    // used when the parsed text starts in the middle of the input line
+    pub fn shifted(mut self, columns: usize) -> Self {
+        self.column += columns;
+        self
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Column {}: expected {}, found {}!",
+            self.column + 1,
+            self.expected,
+            self.found
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// One entry inside the parentheses of a tile list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arg {
+    Number(u8),
+    Color(TileColor),
+    Wildcard,
+    /// A joker pinned to a number in a run, `w7` or `w=7`.
+    JokerNumber(u8),
+    /// A joker pinned to a color in a group, `wr` or `w=red`.
+    JokerColor(TileColor),
+    /// A free joker with the number it was last guessed as, `w~7`, which may still change.
+    JokerGuess(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tail {
+    Number(u8),
+    Color(TileColor),
+}
+
+/// `(args)tail`, e.g. `(3,4,w)r` or `(r,b)10`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileList {
+    pub args: Vec<Arg>,
+    pub tail: Tail,
+}
+
+/// The syntax tree of one command such as `m1>2(7)r`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandAst {
+    pub cmd: Command,
+    pub idx: Option<usize>,
+    pub target: Option<usize>,
+    pub position: Option<usize>,
+    pub replace: Option<TileList>,
+    pub tiles: Option<TileList>,
+    /// The columns of the input line this command was parsed from.
+    pub span: Range<usize>,
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct CommandCapture<'a> {
-    pub cmd: Option<&'a str>,
-    pub idx: Option<&'a str>,
-    pub replace_args: Option<&'a str>,
-    pub replace_tail: Option<&'a str>,
+pub struct PlayerCapture<'a> {
+    pub cmd: &'a str,
+    pub player: Option<&'a str>,
     pub args: Option<&'a str>,
-    pub tail: Option<&'a str>,
+    pub args_column: usize,
+}
+
+/// Recursive-descent parser over the tokens of one input line.
+///
+/// ```text
+/// sequence  := command (';' command)*
+/// command   := ('a' | 'x') index tile_list?
+///            | ('p' | 'd' | 'f') index? tile_list
+///            | 'r' index tile_list tile_list
+///            | 'm' index '>' index tile_list
+///            | 's' index '@' index
+///            | 'j' index '>' index
+/// tile_list := '(' arg (',' arg)* ')' tail
+/// arg       := number (('-' | '..') number)? | color | joker
+/// joker     := 'w' ('='? (number | color))?
+/// init      := hand_tiles+
+/// sets      := hand_tiles* (';' hand_tiles*)*
+/// hand_tiles:= 'w' | color ('(' hand_arg ')' | hand_arg | ':' list_arg (',' list_arg)*)
+/// list_arg  := number (('-' | '..') number)? | hand_arg
+/// hand_arg  := number | 'w' (('=' | '~')? number)?
+/// color     := 'r' | 'b' | 'o' | 'h' | 'red' | 'blue' | 'orange' | 'black'
+/// ```
+///
+/// Words are matched case-insensitively and whitespace may appear between any tokens.
+pub struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    end: usize,
 }
 
 impl Parser {
-    pub fn new() -> Self {
-        let pattern = Regex::new(
-            r"(?x)^
-                (?P<cmd>[adpr])          
-                (?P<idx>\d+|d)?           
-                (?:                      
-                    \(
-                        (?P<replace_args>[^)]*)
-                    \)
-                    (?P<replace_tail>[broh]|\d+)
-                )?                       
-                \(
-                    (?P<args>[^)]*)
-                \)
-                (?P<tail>[broh]|\d+)
-            $",
-        )
-        .unwrap();
+    pub fn new(input: &str) -> Self {
+        Parser {
+            tokens: tokenize(input),
+            pos: 0,
+            end: 0,
+        }
+    }
 
-        let init_pattern = Regex::new(r"(?P<color>[broh])\((?P<number>\d+|[w])\)").unwrap();
+    /// Parse a `;` separated line, empty commands between separators are skipped.
+    pub fn parse_sequence(&mut self) -> Result<Vec<CommandAst>, ParseError> {
+        let mut commands = Vec::new();
 
-        Parser {
-            pattern,
-            init_pattern,
+        loop {
+            match self.peek() {
+                TokenKind::End => break,
+                TokenKind::Semicolon => {
+                    self.advance();
+                }
+                _ => {
+                    commands.push(self.parse_command()?);
+
+                    if !matches!(self.peek(), TokenKind::Semicolon | TokenKind::End) {
+                        return Err(self.error("';' or end of input"));
+                    }
+                }
+            }
         }
+
+        if commands.is_empty() {
+            return Err(self.error("a command"));
+        }
+
+        Ok(commands)
     }
 
-    /// Parse one command string and return named captures if matched
-    pub fn parse<'a>(&self, input: &'a str) -> Option<CommandCapture<'a>> {
-        let captures = self.pattern.captures(input)?;
+    /// Parse a hand such as `r(1)b(2)o(w)`, `red 1 blue 2`, `r3 r4 b10 w` or `r:3,4,5 b:10`,
+    /// every tile is added to the hand. Parsing resumes at the next tile after an error,
+    /// so every invalid token is reported.
+    pub fn parse_init(&mut self) -> Result<Vec<CommandAst>, Vec<ParseError>> {
+        if *self.peek() == TokenKind::End {
+            return Err(vec![self.error(HAND_TILE)]);
+        }
 
-        Some(CommandCapture {
-            cmd: captures.name("cmd").map(|m| m.as_str()),
-            idx: captures.name("idx").map(|m| m.as_str()),
-            replace_args: captures.name("replace_args").map(|m| m.as_str()),
-            replace_tail: captures.name("replace_tail").map(|m| m.as_str()),
-            args: captures.name("args").map(|m| m.as_str()),
-            tail: captures.name("tail").map(|m| m.as_str()),
-        })
+        self.parse_set()
     }
 
-    pub fn parse_init<'a>(&self, input: &'a str) -> Option<Vec<CommandCapture<'a>>> {
-        let captures = self.init_pattern.captures_iter(input);
-        let mut commands = Vec::new();
+    /// Parse the tiles of one set in the notation of a hand, the set may be empty.
+    pub fn parse_set(&mut self) -> Result<Vec<CommandAst>, Vec<ParseError>> {
+        let (commands, errors) = self.parse_tiles(false);
 
-        for capture in captures {
-            let color = capture.name("color")?.as_str();
-            let number = capture.name("number")?.as_str();
-
-            commands.push(CommandCapture {
-                cmd: Some("a"),
-                idx: Some("0"),
-                replace_args: None,
-                replace_tail: None,
-                args: Some(number),
-                tail: Some(color),
-            });
+        if errors.is_empty() {
+            Ok(commands)
+        } else {
+            Err(errors)
         }
+    }
 
-        if commands.is_empty() {
-            return None;
+    /// Parse sets of tiles separated by `;`, such as `r3 r4 w; b10 h10 o10`, any set may be empty.
+    pub fn parse_sets(&mut self) -> Result<Vec<Vec<CommandAst>>, Vec<ParseError>> {
+        let mut sets = Vec::new();
+        let mut errors = Vec::new();
+
+        loop {
+            let (commands, set_errors) = self.parse_tiles(true);
+            sets.push(commands);
+            errors.extend(set_errors);
+
+            if *self.peek() != TokenKind::Semicolon {
+                break;
+            }
+
+            self.advance();
+        }
+
+        if errors.is_empty() {
+            Ok(sets)
         } else {
-            Some(commands)
+            Err(errors)
         }
     }
-}
 
-impl CommandCapture<'_> {
-    pub fn as_tile_command(&self) -> Result<TileCommand, TileCommandError> {
-        let cmd = match self.cmd {
-            Some("a") => Ok(Command::Add),
-            Some("p") => Ok(Command::Put),
-            Some("d") => Ok(Command::Draw),
-            Some("r") => Ok(Command::Replace),
-            _ => Err(TileCommandError::InvalidCommand),
-        }?;
+    /// Parse a single tile such as `r3`, `w` or `r(w7)`.
+    pub fn parse_tile(&mut self) -> Result<CommandAst, ParseError> {
+        let command = self.parse_hand_tiles()?;
+
+        let count = command.tiles.as_ref().map_or(0, |list| list.args.len());
+
+        if count != 1 {
+            return Err(ParseError {
+                column: command.span.start,
+                expected: "a single tile".to_string(),
+                found: format!("{} tiles", count),
+            });
+        }
+
+        self.expect(TokenKind::End, "end of input")?;
+
+        Ok(command)
+    }
+
+    // hand tiles up to the end of the input, or the next `;` if `until_semicolon` is set
+    fn parse_tiles(&mut self, until_semicolon: bool) -> (Vec<CommandAst>, Vec<ParseError>) {
+        let mut commands = Vec::new();
+        let mut errors = Vec::new();
+        let is_end = |kind: &TokenKind| {
+            *kind == TokenKind::End || until_semicolon && *kind == TokenKind::Semicolon
+        };
+
+        while !is_end(self.peek()) {
+            let pos = self.pos;
+
+            match self.parse_hand_tiles() {
+                Ok(command) => commands.push(command),
+                Err(e) => {
+                    errors.push(e);
+
+                    if self.pos == pos {
+                        self.advance();
+                    }
+
+                    while !is_end(self.peek()) && !self.is_hand_start() {
+                        self.advance();
+                    }
+                }
+            }
+        }
+
+        (commands, errors)
+    }
+
+    fn parse_hand_tiles(&mut self) -> Result<CommandAst, ParseError> {
+        let start = self.column();
+
+        let (args, color) = match self.peek() {
+            TokenKind::Word(word) if word == "w" => {
+                self.advance();
+                (vec![Arg::Wildcard], TileColor::Red)
+            }
+            _ if !self.is_hand_start() => return Err(self.error(HAND_TILE)),
+            _ => {
+                let color = self.parse_color()?;
+
+                let args = match self.peek() {
+                    TokenKind::LParen => {
+                        self.advance();
+                        let arg = self.parse_hand_arg()?;
+                        self.expect(TokenKind::RParen, "')'")?;
+                        vec![arg]
+                    }
+                    TokenKind::Colon => {
+                        self.advance();
+                        let mut args = Vec::new();
 
-        let idx = match self.idx {
-            Some(idx) => {
-                let idx = idx
-                    .parse::<usize>()
-                    .map_err(|_| TileCommandError::InvalidIndex)?;
+                        loop {
+                            match self.peek() {
+                                TokenKind::Number(_) => args.extend(self.parse_arg()?),
+                                _ => args.push(self.parse_hand_arg()?),
+                            }
 
-                Ok(idx)
+                            if *self.peek() != TokenKind::Comma {
+                                break;
+                            }
+
+                            self.advance();
+                        }
+
+                        args
+                    }
+                    TokenKind::Number(_) => vec![self.parse_hand_arg()?],
+                    TokenKind::Word(word) if word == "w" => vec![self.parse_hand_arg()?],
+                    _ => return Err(self.error("'(', ':', a number 1-13 or 'w'")),
+                };
+
+                (args, color)
             }
+        };
 
-            None if cmd == Command::Draw => Ok(0),
+        Ok(CommandAst {
+            cmd: Command::Add,
+            idx: Some(0),
+            target: None,
+            position: None,
+            replace: None,
+            tiles: Some(TileList {
+                args,
+                tail: Tail::Color(color),
+            }),
+            span: start..self.end,
+        })
+    }
 
-            _ => Ok(usize::MAX),
-        }?;
+    fn parse_hand_arg(&mut self) -> Result<Arg, ParseError> {
+        match self.peek() {
+            TokenKind::Number(_) => Ok(Arg::Number(self.parse_number()?)),
+            TokenKind::Word(word) if word == "w" => {
+                self.advance();
 
-        let replace_args = self
-            .replace_args
-            .map(|args| args.to_string().split(',').map(|s| s.to_string()).collect());
+                // only a number may follow, a color would start the next tile
+                let prefix = self.peek().clone();
 
-        let replace_tail = self.replace_tail.map(|tail| tail.to_string());
+                if matches!(prefix, TokenKind::Equals | TokenKind::Tilde) {
+                    self.advance();
+                }
 
-        if cmd == Command::Replace && (replace_args.is_none() || replace_tail.is_none()) {
-            return Err(TileCommandError::Other(String::from(
-                "Invalid replace command format!",
-            )));
+                match (self.peek(), prefix) {
+                    // `w~7` is a free joker the solver last placed as 7
+                    (TokenKind::Number(_), TokenKind::Tilde) => {
+                        Ok(Arg::JokerGuess(self.parse_number()?))
+                    }
+                    (TokenKind::Number(_), _) => Ok(Arg::JokerNumber(self.parse_number()?)),
+                    (_, TokenKind::Equals | TokenKind::Tilde) => {
+                        Err(self.error("a number 1-13 for the joker"))
+                    }
+                    _ => Ok(Arg::Wildcard),
+                }
+            }
+            _ => Err(self.error("a number 1-13 or 'w'")),
         }
+    }
 
-        let args = self
-            .args
-            .map(|args| args.to_string().split(',').map(|s| s.to_string()).collect())
-            .ok_or(TileCommandError::InvalidArgs)?;
+    // a tile of a hand starts with a color or a joker
+    fn is_hand_start(&self) -> bool {
+        match self.peek() {
+            TokenKind::Word(word) => word == "w" || TileColor::str_to_tile_color(word).is_some(),
+            _ => false,
+        }
+    }
 
-        let tail = self
-            .tail
-            .map(|tail| tail.to_string())
-            .ok_or(TileCommandError::InvalidTail)?;
+    fn parse_command(&mut self) -> Result<CommandAst, ParseError> {
+        const EXPECTED: &str = "a command (a, p, d, r, f, m, x, s or j)";
+
+        let start = self.column();
+        let cmd = match self.peek() {
+            TokenKind::Word(word) => TILE_COMMANDS
+                .iter()
+                .find(|(name, _)| name == word)
+                .map(|(_, cmd)| cmd.clone()),
+            _ => None,
+        }
+        .ok_or_else(|| self.error(EXPECTED))?;
+        self.advance();
 
-        Ok(TileCommand {
+        let mut command = CommandAst {
             cmd,
-            idx,
-            args,
+            idx: None,
+            target: None,
+            position: None,
+            replace: None,
+            tiles: None,
+            span: start..start,
+        };
+
+        match command.cmd {
+            Command::Put | Command::Draw | Command::Play => {
+                if let TokenKind::Number(_) = self.peek() {
+                    command.idx = Some(self.parse_index()?);
+                }
+                command.tiles = Some(self.parse_tile_list()?);
+            }
+
+            Command::Replace => {
+                command.idx = Some(self.parse_index()?);
+                command.replace = Some(self.parse_tile_list()?);
+                command.tiles = Some(self.parse_tile_list()?);
+            }
+
+            Command::Move => {
+                command.idx = Some(self.parse_index()?);
+                self.expect(TokenKind::Greater, "'>' and the target index")?;
+                command.target = Some(self.parse_index()?);
+                command.tiles = Some(self.parse_tile_list()?);
+            }
+
+            // without a tile list `x` deletes the whole set
+            Command::Remove => {
+                command.idx = Some(self.parse_index()?);
+
+                if *self.peek() == TokenKind::LParen {
+                    command.tiles = Some(self.parse_tile_list()?);
+                } else {
+                    command.cmd = Command::Delete;
+                }
+            }
+
+            Command::Split => {
+                command.idx = Some(self.parse_index()?);
+                self.expect(TokenKind::At, "'@' and the split position")?;
+                command.position = Some(self.parse_index()?);
+            }
+
+            Command::Merge => {
+                command.idx = Some(self.parse_index()?);
+                self.expect(TokenKind::Greater, "'>' and the target index")?;
+                command.target = Some(self.parse_index()?);
+            }
+
+            _ => {
+                command.idx = Some(self.parse_index()?);
+                command.tiles = Some(self.parse_tile_list()?);
+            }
+        }
+
+        command.span = start..self.end;
+
+        Ok(command)
+    }
+
+    fn parse_tile_list(&mut self) -> Result<TileList, ParseError> {
+        self.expect(TokenKind::LParen, "'(' and a tile list")?;
+
+        let mut args = Vec::new();
+
+        loop {
+            let token = self.tokens[self.pos].clone();
+
+            for arg in self.parse_arg()? {
+                args.push((arg, token.clone()));
+            }
+
+            match self.peek() {
+                TokenKind::Comma => {
+                    self.advance();
+                }
+                TokenKind::RParen => {
+                    self.advance();
+                    break;
+                }
+                _ => return Err(self.error("',' or ')'")),
+            }
+        }
+
+        let tail = self.parse_tail()?;
+
+        // a color tail takes numbers and a number tail takes colors
+        for (arg, token) in &args {
+            let expected = match (tail, arg) {
+                (Tail::Color(_), Arg::Color(_) | Arg::JokerColor(_)) => {
+                    "a number 1-13 or 'w' before a color tail"
+                }
+                (Tail::Number(_), Arg::Number(_) | Arg::JokerNumber(_)) => {
+                    "a color (r, b, o, h) or 'w' before a number tail"
+                }
+                _ => continue,
+            };
+
+            return Err(ParseError {
+                column: token.column,
+                expected: expected.to_string(),
+                found: token.kind.to_string(),
+            });
+        }
+
+        Ok(TileList {
+            args: args.into_iter().map(|(arg, _)| arg).collect(),
             tail,
-            replace_args,
-            replace_tail,
         })
     }
+
+    // a range such as `3-8` or `10..13` is expanded into one arg per number
+    fn parse_arg(&mut self) -> Result<Vec<Arg>, ParseError> {
+        match self.peek() {
+            TokenKind::Number(_) => {
+                let start = self.parse_number()?;
+
+                if !matches!(self.peek(), TokenKind::Dash | TokenKind::DotDot) {
+                    return Ok(vec![Arg::Number(start)]);
+                }
+
+                self.advance();
+
+                let end = match self.peek() {
+                    TokenKind::Number(number) => number.parse::<u8>().ok(),
+                    _ => None,
+                }
+                .filter(|end| (start..=13).contains(end))
+                .ok_or_else(|| self.error(&format!("a number {}-13 to end the range", start)))?;
+                self.advance();
+
+                Ok((start..=end).map(Arg::Number).collect())
+            }
+            TokenKind::Word(word) if word == "w" => {
+                self.advance();
+                Ok(vec![self.parse_joker()?])
+            }
+            // `wr` is lexed as a single word
+            TokenKind::Word(word) if word.starts_with('w') && word.len() > 1 => {
+                let color = TileColor::str_to_tile_color(&word[1..])
+                    .ok_or_else(|| self.error("'w' followed by a number or a color"))?;
+                self.advance();
+                Ok(vec![Arg::JokerColor(color)])
+            }
+            TokenKind::Word(word) if TileColor::str_to_tile_color(word).is_some() => {
+                Ok(vec![Arg::Color(self.parse_color()?)])
+            }
+            _ => Err(self.error("a number 1-13, a color (r, b, o, h or the full name) or 'w'")),
+        }
+    }
+
+    // the value a joker is pinned to, after the `w` itself
+    fn parse_joker(&mut self) -> Result<Arg, ParseError> {
+        let has_equals = *self.peek() == TokenKind::Equals;
+
+        if has_equals {
+            self.advance();
+        }
+
+        match self.peek() {
+            TokenKind::Number(_) => Ok(Arg::JokerNumber(self.parse_number()?)),
+            TokenKind::Word(word) if TileColor::str_to_tile_color(word).is_some() => {
+                Ok(Arg::JokerColor(self.parse_color()?))
+            }
+            _ if has_equals => Err(self.error("a number 1-13 or a color for the joker")),
+            _ => Ok(Arg::Wildcard),
+        }
+    }
+
+    fn parse_tail(&mut self) -> Result<Tail, ParseError> {
+        match self.peek() {
+            TokenKind::Number(_) => Ok(Tail::Number(self.parse_number()?)),
+            TokenKind::Word(word) if TileColor::str_to_tile_color(word).is_some() => {
+                Ok(Tail::Color(self.parse_color()?))
+            }
+            _ => Err(self.error("a tail, a number 1-13 or a color (r, b, o, h or the full name)")),
+        }
+    }
+
+    fn parse_color(&mut self) -> Result<TileColor, ParseError> {
+        let color = match self.peek() {
+            TokenKind::Word(word) => TileColor::str_to_tile_color(word),
+            _ => None,
+        }
+        .ok_or_else(|| self.error("a color (r, b, o, h or the full name)"))?;
+        self.advance();
+
+        Ok(color)
+    }
+
+    fn parse_number(&mut self) -> Result<u8, ParseError> {
+        let number = match self.peek() {
+            TokenKind::Number(number) => number.parse::<u8>().ok(),
+            _ => None,
+        }
+        .filter(|number| (1..=13).contains(number))
+        .ok_or_else(|| self.error("a number 1-13"))?;
+        self.advance();
+
+        Ok(number)
+    }
+
+    fn parse_index(&mut self) -> Result<usize, ParseError> {
+        let index = match self.peek() {
+            TokenKind::Number(number) => number.parse::<usize>().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| self.error("an index"))?;
+        self.advance();
+
+        Ok(index)
+    }
+
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.pos].kind
+    }
+
+    fn column(&self) -> usize {
+        self.tokens[self.pos].column
+    }
+
+    fn advance(&mut self) {
+        let token = &self.tokens[self.pos];
+
+        if token.kind != TokenKind::End {
+            self.end = token.column + token.kind.width();
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> Result<(), ParseError> {
+        if *self.peek() != kind {
+            return Err(self.error(expected));
+        }
+
+        self.advance();
+
+        Ok(())
+    }
+
+    fn error(&self, expected: &str) -> ParseError {
+        let token = &self.tokens[self.pos];
+
+        ParseError {
+            column: token.column,
+            expected: expected.to_string(),
+            found: token.kind.to_string(),
+        }
+    }
 }
 
-pub fn commands_capture_to_tile_commands(
-    commands: Vec<CommandCapture<'_>>,
-    game: &Game,
-) -> Result<Vec<TileCommand>, TileCommandError> {
-    let commands = commands
+/// Parse an initial hand such as `r3 r4 b10 w`, every tile becomes an `Add` to index 0.
+pub fn parse_init(input: &str) -> Result<Vec<CommandAst>, Vec<ParseError>> {
+    Parser::new(input).parse_init()
+}
+
+/// Like `parse_init`, but an empty set is accepted.
+pub fn parse_set(input: &str) -> Result<Vec<CommandAst>, Vec<ParseError>> {
+    Parser::new(input).parse_set()
+}
+
+/// Parse sets in the notation of a hand separated by `;`, such as `r3 r4 w; b10 h10 o10`.
+pub fn parse_sets(input: &str) -> Result<Vec<Vec<CommandAst>>, Vec<ParseError>> {
+    Parser::new(input).parse_sets()
+}
+
+/// Parse exactly one tile such as `r3`, `w` or `r(w7)`.
+pub fn parse_tile(input: &str) -> Result<CommandAst, ParseError> {
+    Parser::new(input).parse_tile()
+}
+
+/// Parse the `hand r3 r4 b:10,11` command, which adds tiles to the hand (index 0).
+/// Returns `None` if the input is not a hand command.
+pub fn parse_hand(input: &str) -> Option<Result<Vec<CommandAst>, Vec<ParseError>>> {
+    let trimmed = input.trim_start();
+    let (cmd, rest) = trimmed
+        .split_once(char::is_whitespace)
+        .unwrap_or((trimmed, ""));
+
+    if !cmd.eq_ignore_ascii_case("hand") {
+        return None;
+    }
+
+    let column = input[..input.len() - rest.len()].chars().count();
+
+    Some(parse_init(rest).map_err(|errors| errors.into_iter().map(|e| e.shifted(column)).collect()))
+}
+
+/// Every accepted command as listed in error messages, e.g. `'a'/'p'/... or join/next/...`.
+pub fn command_list() -> String {
+    let tile_commands = TILE_COMMANDS
         .iter()
-        .map(|cmd| cmd.as_tile_command())
-        .collect::<Vec<_>>();
+        .map(|(name, _)| format!("'{}'", name))
+        .collect::<Vec<_>>()
+        .join("/");
 
-    let mut tile_command_error = TileCommandError::Other("Default error message".to_string());
-    let is_error = commands.iter().any(|cmd| match cmd {
-        Ok(_) => false,
-        Err(e) => {
-            tile_command_error = e.clone();
-            true
-        }
-    });
+    format!("{} or {}", tile_commands, PLAYER_COMMANDS.join("/"))
+}
 
-    if is_error {
-        return Err(tile_command_error);
+/// Parse a player/turn command such as `join Alice 14` or `took 1 r(3)b(4)`
+pub fn parse_player(input: &str) -> Option<PlayerCapture<'_>> {
+    fn split_word(s: &str) -> (&str, &str) {
+        let s = s.trim_start();
+        s.split_once(char::is_whitespace).unwrap_or((s, ""))
     }
 
-    let commands = commands
-        .into_iter()
-        .filter_map(Result::ok)
-        .collect::<Vec<_>>();
+    let (cmd, rest) = split_word(input);
+    // keywords ignore case like the tile commands, `cmd` is always the lowercase keyword
+    let cmd = *PLAYER_COMMANDS
+        .iter()
+        .find(|keyword| keyword.eq_ignore_ascii_case(cmd))?;
+
+    let (player, rest) = split_word(rest);
+    let args = rest.trim();
+    // `rest` is always a suffix of the input
+    let offset = input.len() - rest.trim_start().len();
+
+    Some(PlayerCapture {
+        cmd,
+        player: (!player.is_empty()).then_some(player),
+        args: (!args.is_empty()).then_some(args),
+        args_column: input[..offset].chars().count(),
+    })
+}
+
+impl PlayerCapture<'_> {
+    pub fn as_player_command(&self) -> Result<PlayerCommand, TileCommandError> {
+        if self.cmd == "next" {
+            return match self.player {
+                None => Ok(PlayerCommand::NextTurn),
+                Some(_) => Err(TileCommandError::InvalidArgs),
+            };
+        }
 
-    let commands = commands
-        .into_iter()
-        .map(|cmd| cmd.validate(game))
-        .collect::<Vec<_>>();
+        let player = self.player.ok_or(TileCommandError::InvalidIndex)?;
 
-    let mut tile_command_error = TileCommandError::Other("Default error message".to_string());
-    let is_error = commands.iter().any(|cmd| match cmd {
-        Ok(_) => false,
-        Err(e) => {
-            tile_command_error = e.clone();
-            true
+        if self.cmd == "join" {
+            let tile_count = self.parse_count(14)?;
+
+            return Ok(PlayerCommand::Join {
+                name: player.to_string(),
+                tile_count,
+            });
         }
-    });
 
-    if is_error {
-        return Err(tile_command_error);
+        let player = player
+            .parse::<usize>()
+            .map_err(|_| TileCommandError::InvalidIndex)?;
+
+        match self.cmd {
+            "drew" => Ok(PlayerCommand::Drew {
+                player,
+                count: self.parse_count(1)?,
+            }),
+            "played" => Ok(PlayerCommand::Played {
+                player,
+                count: self.parse_count(1)?,
+            }),
+            "meld" => Ok(PlayerCommand::Meld { player }),
+            "took" => {
+                let args = self.args.ok_or(TileCommandError::InvalidArgs)?;
+                let tiles = parse_init(args)
+                    .map_err(|errors| {
+                        TileCommandError::Syntax(
+                            errors
+                                .into_iter()
+                                .map(|e| e.shifted(self.args_column))
+                                .collect(),
+                        )
+                    })?
+                    .iter()
+                    .map(CommandAst::to_tiles)
+                    .collect::<Result<Vec<_>, _>>()?
+                    .concat();
+
+                Ok(PlayerCommand::PickedUp { player, tiles })
+            }
+            _ => Err(TileCommandError::InvalidCommand),
+        }
     }
 
-    let commands = commands
-        .into_iter()
-        .filter_map(Result::ok)
-        .collect::<Vec<_>>();
+    fn parse_count(&self, default: usize) -> Result<usize, TileCommandError> {
+        match self.args {
+            Some(args) => args
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| TileCommandError::InvalidArgs),
+            None => Ok(default),
+        }
+    }
+}
 
-    Ok(commands)
+impl CommandAst {
+    /// The tiles of the command's tile list, without checking them against a board.
+    pub fn to_tiles(&self) -> Result<Vec<Tile>, TileCommandError> {
+        self.tiles
+            .as_ref()
+            .map_or(Ok(Vec::new()), TileList::to_tiles)
+    }
 }
 
-pub fn command_capture_to_tile_command(
-    command: CommandCapture<'_>,
+/// Validate every command against the same board, see `TileCommand::validate`.
+pub fn commands_to_tile_commands(
+    commands: &[CommandAst],
+    game: &Game,
+) -> Result<Vec<TileCommand>, TileCommandError> {
+    commands
+        .iter()
+        .map(|command| command_to_tile_command(command, game))
+        .collect()
+}
+
+/// Validate one parsed command against the board.
+pub fn command_to_tile_command(
+    command: &CommandAst,
     game: &Game,
 ) -> Result<TileCommand, TileCommandError> {
-    let command = command.as_tile_command()?;
-    let tile_command = command.validate(game)?;
+    TileCommand::validate(command, game)
+}
+
+/// Validate and convert a `;` separated line such as `p(3,4,5)r;a1(6)r`.
+/// Each command is validated against the board left by the ones before it, and
+/// nothing is returned unless every command is valid.
+pub fn command_sequence_to_operations(
+    input: &str,
+    game: &Game,
+) -> Result<Vec<GameOperation>, TileCommandError> {
+    let commands = Parser::new(input)
+        .parse_sequence()
+        .map_err(|e| TileCommandError::Syntax(vec![e]))?;
+    let chars = input.chars().collect::<Vec<_>>();
+
+    let mut game = game.clone();
+    let mut operations = Vec::new();
+
+    for (i, command) in commands.iter().enumerate() {
+        let operation = command_to_tile_command(command, &game).and_then(|tile_command| {
+            let operation = GameOperation::from(tile_command);
+            game.operate(operation.clone())?;
+            Ok(operation)
+        });
+
+        let operation = match operation {
+            Ok(operation) => operation,
+            Err(e) if commands.len() > 1 => {
+                return Err(TileCommandError::Sequence {
+                    position: i + 1,
+                    command: chars[command.span.clone()].iter().collect(),
+                    error: Box::new(e),
+                })
+            }
+            Err(e) => return Err(e),
+        };
+
+        operations.push(operation);
+    }
 
-    Ok(tile_command)
+    Ok(operations)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::game::ToTiles;
-
     use super::*;
+    use crate::game::{tile::Tile, tile_color::TileColor};
+
+    fn parse(input: &str) -> Result<CommandAst, ParseError> {
+        Parser::new(input).parse_sequence().map(|mut commands| {
+            assert_eq!(commands.len(), 1);
+            commands.remove(0)
+        })
+    }
+
+    fn list(args: Vec<Arg>, tail: Tail) -> Option<TileList> {
+        Some(TileList { args, tail })
+    }
 
     #[test]
     fn test_parse_simple() {
-        let p = Parser::new();
-        let cmd = p.parse("a0(3)r").expect("should parse");
-        assert_eq!(cmd.idx, Some("0"));
-        assert_eq!(cmd.cmd, Some("a"));
-        assert_eq!(cmd.args, Some("3"));
-        assert_eq!(cmd.tail, Some("r"));
+        let cmd = parse("a0(3)r").expect("should parse");
+        assert_eq!(cmd.idx, Some(0));
+        assert_eq!(cmd.cmd, Command::Add);
+        assert_eq!(
+            cmd.tiles,
+            list(vec![Arg::Number(3)], Tail::Color(TileColor::Red))
+        );
+        assert_eq!(cmd.span, 0..6);
 
-        println!("{:?}", cmd.as_tile_command());
+        println!("{:?}", cmd.to_tiles());
     }
 
     #[test]
     fn test_parse_2() {
-        let p = Parser::new();
-        let cmd = p.parse("a1(1,2)b").expect("should parse");
-        assert_eq!(cmd.idx, Some("1"));
-        assert_eq!(cmd.cmd, Some("a"));
-        assert_eq!(cmd.args, Some("1,2"));
-        assert_eq!(cmd.tail, Some("b"));
-        println!("{:?}", cmd.as_tile_command());
+        let cmd = parse("a1(1,2)b").expect("should parse");
+        assert_eq!(cmd.idx, Some(1));
+        assert_eq!(cmd.cmd, Command::Add);
+        assert_eq!(
+            cmd.tiles,
+            list(
+                vec![Arg::Number(1), Arg::Number(2)],
+                Tail::Color(TileColor::Blue)
+            )
+        );
+        println!("{:?}", cmd.to_tiles());
     }
 
     #[test]
     fn test_parse_3() {
-        let p = Parser::new();
-        let cmd = p.parse("a1(r,b)2").expect("should parse");
-        assert_eq!(cmd.idx, Some("1"));
-        assert_eq!(cmd.cmd, Some("a"));
-        assert_eq!(cmd.args, Some("r,b"));
-        assert_eq!(cmd.tail, Some("2"));
-        println!("{:?}", cmd.as_tile_command());
+        let cmd = parse("a1(r,b)2").expect("should parse");
+        assert_eq!(cmd.idx, Some(1));
+        assert_eq!(cmd.cmd, Command::Add);
+        assert_eq!(
+            cmd.tiles,
+            list(
+                vec![Arg::Color(TileColor::Red), Arg::Color(TileColor::Blue)],
+                Tail::Number(2)
+            )
+        );
+        println!("{:?}", cmd.to_tiles());
     }
 
     #[test]
     fn test_parse_4() {
-        let p = Parser::new();
-        let cmd = p.parse("a1(11,12,w)b").expect("should parse");
+        let cmd = parse("a1(11,12,w)b").expect("should parse");
 
-        assert_eq!(cmd.idx, Some("1"));
-        assert_eq!(cmd.cmd, Some("a"));
-        assert_eq!(cmd.args, Some("11,12,w"));
-        assert_eq!(cmd.tail, Some("b"));
+        assert_eq!(cmd.idx, Some(1));
+        assert_eq!(cmd.cmd, Command::Add);
+        assert_eq!(
+            cmd.tiles,
+            list(
+                vec![Arg::Number(11), Arg::Number(12), Arg::Wildcard],
+                Tail::Color(TileColor::Blue)
+            )
+        );
     }
 
     #[test]
     fn test_parse_5() {
-        let p = Parser::new();
-        let cmd = p.parse("a1(r,b,w)10").expect("should parse");
+        let cmd = parse("a1(r,b,w)10").expect("should parse");
 
-        assert_eq!(cmd.idx, Some("1"));
-        assert_eq!(cmd.cmd, Some("a"));
-        assert_eq!(cmd.args, Some("r,b,w"));
-        assert_eq!(cmd.tail, Some("10"));
+        assert_eq!(cmd.idx, Some(1));
+        assert_eq!(cmd.cmd, Command::Add);
+        assert_eq!(
+            cmd.tiles,
+            list(
+                vec![
+                    Arg::Color(TileColor::Red),
+                    Arg::Color(TileColor::Blue),
+                    Arg::Wildcard
+                ],
+                Tail::Number(10)
+            )
+        );
 
-        println!("{:?}", cmd.as_tile_command());
-
-        println!("{:?}", cmd.as_tile_command().unwrap().to_tiles());
+        let tiles = cmd.to_tiles().expect("should convert");
+        assert_eq!(tiles[1], Tile::new(10, TileColor::Blue, false));
+        assert!(tiles[2].is_free_wildcard());
     }
 
     #[test]
     fn test_parse_6() {
-        let p = Parser::new();
-        let cmd = p.parse("r2(11)h(r,o)2").expect("should parse");
-
-        assert_eq!(cmd.idx, Some("2"));
-        assert_eq!(cmd.cmd, Some("r"));
-        assert_eq!(cmd.replace_args, Some("11"));
-        assert_eq!(cmd.replace_tail, Some("h"));
-        assert_eq!(cmd.args, Some("r,o"));
-        assert_eq!(cmd.tail, Some("2"));
+        let cmd = parse("r2(11)h(r,o)2").expect("should parse");
 
-        println!("{:?}", cmd.as_tile_command());
+        assert_eq!(cmd.idx, Some(2));
+        assert_eq!(cmd.cmd, Command::Replace);
+        assert_eq!(
+            cmd.replace,
+            list(vec![Arg::Number(11)], Tail::Color(TileColor::Black))
+        );
+        assert_eq!(
+            cmd.tiles,
+            list(
+                vec![Arg::Color(TileColor::Red), Arg::Color(TileColor::Orange)],
+                Tail::Number(2)
+            )
+        );
 
-        println!("{:?}", cmd.as_tile_command().unwrap().to_tiles());
+        let tile_command = command_to_tile_command(&cmd, &Game::new());
+        assert_eq!(tile_command, Err(TileCommandError::InvalidIndex));
     }
 
     #[test]
     fn test_parse_draw() {
-        let p = Parser::new();
-        let cmd = p.parse("d(10)r");
+        let cmd = parse("d(10)r");
 
         println!("{:?}", cmd);
         let cmd = cmd.expect("should parse");
 
-        assert_eq!(cmd.cmd, Some("d"));
-        assert_eq!(cmd.args, Some("10"));
-        assert_eq!(cmd.tail, Some("r"));
-
-        let tile_command = cmd
-            .as_tile_command()
-            .expect("should convert to TileCommand");
+        assert_eq!(cmd.cmd, Command::Draw);
+        assert_eq!(
+            cmd.tiles,
+            list(vec![Arg::Number(10)], Tail::Color(TileColor::Red))
+        );
 
-        println!("{:?}", cmd.as_tile_command());
+        let tile_command = command_to_tile_command(&cmd, &Game::new());
 
-        assert!(tile_command.cmd == Command::Draw);
-        assert!(tile_command.idx == 0);
-        assert!(tile_command.args == vec!["10".to_string()]);
-        assert!(tile_command.tail == "r".to_string());
+        assert_eq!(
+            tile_command,
+            Ok(TileCommand::Draw {
+                index: 0,
+                tiles: vec![Tile::new(10, TileColor::Red, false)]
+            })
+        );
     }
 
     #[test]
     fn test_parse_draw2() {
-        let p = Parser::new();
-        let cmd = p.parse("d(10)h").expect("should parse");
-        assert_eq!(cmd.cmd, Some("d"));
-        assert_eq!(cmd.args, Some("10"));
-        assert_eq!(cmd.tail, Some("h"));
-
-        let tile_command = cmd
-            .as_tile_command()
-            .expect("should convert to TileCommand");
-        println!("{:?}", cmd.as_tile_command());
-        assert!(tile_command.cmd == Command::Draw);
-        assert!(tile_command.idx == 0);
-        assert!(tile_command.args == vec!["10".to_string()]);
-        assert!(tile_command.tail == "h".to_string());
+        let cmd = parse("d(10)h").expect("should parse");
+        assert_eq!(cmd.cmd, Command::Draw);
+
+        let tile_command = command_to_tile_command(&cmd, &Game::new());
+        assert_eq!(
+            tile_command,
+            Ok(TileCommand::Draw {
+                index: 0,
+                tiles: vec![Tile::new(10, TileColor::Black, false)]
+            })
+        );
     }
 
     #[test]
     fn test_parse_put() {
-        let p = Parser::new();
-        let cmd = p.parse("p(9,10,11)h").expect("should parse");
-        assert_eq!(cmd.cmd, Some("p"));
-        assert_eq!(cmd.args, Some("9,10,11"));
-        assert_eq!(cmd.tail, Some("h"));
-        println!("{:?}", cmd.as_tile_command());
+        let cmd = parse("p(9,10,11)h").expect("should parse");
+        assert_eq!(cmd.cmd, Command::Put);
+        assert_eq!(
+            cmd.tiles,
+            list(
+                vec![Arg::Number(9), Arg::Number(10), Arg::Number(11)],
+                Tail::Color(TileColor::Black)
+            )
+        );
+        println!("{:?}", cmd.to_tiles());
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let expected = parse("a0(3,4)r").unwrap().tiles;
+
+        for input in ["a0( 3, 4 )r", "A0(3,4)R", " a 0 (3 ,4) red", "a0(3,4)Red"] {
+            assert_eq!(parse(input).expect(input).tiles, expected, "{}", input);
+        }
+
+        let cmd = parse("P(Red, BLUE, black, W) 10").expect("should parse");
+        assert_eq!(
+            cmd.tiles,
+            list(
+                vec![
+                    Arg::Color(TileColor::Red),
+                    Arg::Color(TileColor::Blue),
+                    Arg::Color(TileColor::Black),
+                    Arg::Wildcard
+                ],
+                Tail::Number(10)
+            )
+        );
+
+        let cmd = parse("M1 > 2 (7) r").expect("should parse");
+        assert_eq!((cmd.cmd, cmd.target), (Command::Move, Some(2)));
+
+        for input in ["JOIN Alice", "Next", "Drew 1 2"] {
+            let capture = parse_player(input).expect(input);
+            assert!(capture.as_player_command().is_ok(), "{}", input);
+        }
+        assert_eq!(parse_player("Join Alice").unwrap().player, Some("Alice"));
+    }
+
+    #[test]
+    fn test_parse_ranges() {
+        let numbers = |numbers: &[u8]| numbers.iter().map(|&n| Arg::Number(n)).collect();
+
+        let cmd = parse("p(3-8)b").expect("should parse");
+        assert_eq!(
+            cmd.tiles,
+            list(numbers(&[3, 4, 5, 6, 7, 8]), Tail::Color(TileColor::Blue))
+        );
+
+        let cmd = parse("a2(10..13)h").expect("should parse");
+        assert_eq!(
+            cmd.tiles,
+            list(numbers(&[10, 11, 12, 13]), Tail::Color(TileColor::Black))
+        );
+
+        let cmd = parse("p(3-5,w,7)r").expect("should parse");
+        let mut args = numbers(&[3, 4, 5]);
+        args.extend([Arg::Wildcard, Arg::Number(7)]);
+        assert_eq!(cmd.tiles, list(args, Tail::Color(TileColor::Red)));
+
+        assert_eq!(
+            parse("p(5-5)r").unwrap().tiles,
+            list(numbers(&[5]), Tail::Color(TileColor::Red))
+        );
+
+        let e = parse("p(10-14)b").unwrap_err();
+        assert_eq!(e.column, 5);
+        assert_eq!(e.expected, "a number 10-13 to end the range");
+
+        let e = parse("p(8-3)b").unwrap_err();
+        assert_eq!((e.column, e.found.as_str()), (4, "'3'"));
+
+        let e = parse("p(0..3)b").unwrap_err();
+        assert_eq!((e.column, e.expected.as_str()), (2, "a number 1-13"));
+
+        let e = parse("p(3-)b").unwrap_err();
+        assert_eq!(e.found, "')'");
+
+        // a range is a run of numbers, so it needs a color tail
+        let e = parse("p(3-5)10").unwrap_err();
+        assert_eq!(e.column, 2);
+    }
+
+    #[test]
+    fn test_parse_joker_assignment() {
+        for input in ["p(5,6,w=7)r", "p(5,6,w7)r", "p(5, 6, W = 7)red"] {
+            assert_eq!(
+                parse(input).expect(input).tiles,
+                list(
+                    vec![Arg::Number(5), Arg::Number(6), Arg::JokerNumber(7)],
+                    Tail::Color(TileColor::Red)
+                ),
+                "{}",
+                input
+            );
+        }
+
+        for input in [
+            "p(r,b,wh)10",
+            "p(r,b,w=h)10",
+            "p(r,b,w=black)10",
+            "p(r,b,wblack)10",
+        ] {
+            assert_eq!(
+                parse(input).expect(input).tiles,
+                list(
+                    vec![
+                        Arg::Color(TileColor::Red),
+                        Arg::Color(TileColor::Blue),
+                        Arg::JokerColor(TileColor::Black)
+                    ],
+                    Tail::Number(10)
+                ),
+                "{}",
+                input
+            );
+        }
+
+        let cmd = parse("p(5,6,w7)r").unwrap();
+        assert!(command_to_tile_command(&cmd, &Game::new()).is_ok());
+
+        let tiles = cmd.to_tiles().unwrap();
+        assert_eq!(tiles[2], Tile::new_pinned(7, TileColor::Red));
+
+        let tiles = parse("p(r,b,wh)10").unwrap().to_tiles().unwrap();
+        assert_eq!(tiles[2], Tile::new_pinned(10, TileColor::Black));
+
+        let e = parse("p(5,6,w=)r").unwrap_err();
+        assert_eq!(
+            (e.column, e.expected.as_str()),
+            (8, "a number 1-13 or a color for the joker")
+        );
+        assert_eq!(parse("p(5,6,w14)r").unwrap_err().column, 7);
+        assert_eq!(parse("p(5,6,wx)r").unwrap_err().column, 6);
+        assert_eq!(parse("p(r,b,w7)10").unwrap_err().column, 6);
+        assert_eq!(parse("p(5,6,wr)7").unwrap_err().column, 2);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |input: &str| parse(input).expect_err(input);
+
+        let e = error("a0(3)rb");
+        assert_eq!((e.column, e.found.as_str()), (5, "'rb'"));
+
+        let e = error("a0(14)r");
+        assert_eq!(e.column, 3);
+        assert_eq!(e.expected, "a number 1-13");
+
+        let e = error("p(r,3)b");
+        assert_eq!(e.column, 2);
+        assert_eq!(
+            e.to_string(),
+            "Column 3: expected a number 1-13 or 'w' before a color tail, found 'r'!"
+        );
+
+        let e = error("m1(5)r");
+        assert_eq!(
+            (e.column, e.expected.as_str()),
+            (2, "'>' and the target index")
+        );
+
+        let e = error("q1");
+        assert_eq!(e.column, 0);
+
+        let e = error("a0(3 4)r");
+        assert_eq!((e.column, e.expected.as_str()), (5, "',' or ')'"));
+
+        let e = error("a0(3)");
+        assert_eq!(e.found, "end of input");
+
+        for input in [
+            "a0", "x", "s1", "j1", "a1>2(5)r", "s1@3(3)r", "a0()r", "a0(3)r!",
+        ] {
+            assert!(parse(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_play() {
+        let mut game = Game::new();
+        game.board[0] = vec![
+            Tile::new(9, TileColor::Black, false),
+            Tile::new(10, TileColor::Black, false),
+            Tile::new(251, TileColor::Red, true),
+            Tile::new(3, TileColor::Red, false),
+        ];
+
+        let cmd = parse("f(9,10,w)h").expect("should parse");
+        let tile_command = command_to_tile_command(&cmd, &game).expect("hand contains the tiles");
+        assert!(matches!(tile_command, TileCommand::Play { .. }));
+
+        game.operate(tile_command.into()).unwrap();
+        assert_eq!(game.board[0], vec![Tile::new(3, TileColor::Red, false)]);
+        assert_eq!(game.board.len(), 2);
+        assert_eq!(game.board[1].len(), 3);
+
+        let cmd = parse("f(9,10,11)h").expect("should parse");
+        assert!(matches!(
+            command_to_tile_command(&cmd, &game),
+            Err(TileCommandError::NotInRow(0))
+        ));
+    }
+
+    #[test]
+    fn test_parse_move() {
+        let mut game = Game::new_with_board(vec![
+            vec![],
+            (4..=10)
+                .map(|n| Tile::new(n, TileColor::Red, false))
+                .collect(),
+            vec![
+                Tile::new(7, TileColor::Blue, false),
+                Tile::new(7, TileColor::Black, false),
+                Tile::new(7, TileColor::Orange, false),
+            ],
+        ]);
+
+        // b7 does not fit the run and would leave h7 o7 behind
+        let cmd = parse("m2>1(7)b").expect("should parse");
+        assert!(matches!(
+            command_to_tile_command(&cmd, &game),
+            Err(TileCommandError::InvalidSet(_))
+        ));
+
+        let cmd = parse("m1>2(7)r").expect("should parse");
+        assert_eq!(cmd.idx, Some(1));
+        assert_eq!(cmd.target, Some(2));
+
+        let tile_command = command_to_tile_command(&cmd, &game).expect("should validate");
+        assert!(matches!(
+            tile_command,
+            TileCommand::Move { from: 1, to: 2, .. }
+        ));
+
+        game.operate(tile_command.into()).unwrap();
+        assert_eq!(game.board.len(), 4);
+        assert_eq!(game.board[1].len(), 3);
+        assert_eq!(game.board[2].len(), 4);
+        assert_eq!(game.board[3].len(), 3);
+
+        let cmd = parse("m1>2(9)r").expect("should parse");
+        assert!(matches!(
+            command_to_tile_command(&cmd, &game),
+            Err(TileCommandError::NotInRow(1))
+        ));
+
+        let cmd = parse("m1>9(5)r").expect("should parse");
+        assert!(command_to_tile_command(&cmd, &game).is_err());
+    }
+
+    #[test]
+    fn test_parse_remove_and_delete() {
+        let mut game = Game::new_with_board(vec![
+            vec![
+                Tile::new(5, TileColor::Red, false),
+                Tile::new(6, TileColor::Red, false),
+            ],
+            vec![
+                Tile::new(1, TileColor::Blue, false),
+                Tile::new(2, TileColor::Blue, false),
+                Tile::new(3, TileColor::Blue, false),
+            ],
+            vec![
+                Tile::new(9, TileColor::Blue, false),
+                Tile::new(9, TileColor::Red, false),
+                Tile::new(9, TileColor::Black, false),
+            ],
+        ]);
+
+        let cmd = parse("x0(5)r").expect("should parse");
+        let tile_command = command_to_tile_command(&cmd, &game).expect("should validate");
+        assert!(matches!(tile_command, TileCommand::Remove { index: 0, .. }));
+        game.operate(tile_command.into()).unwrap();
+        assert_eq!(game.board[0], vec![Tile::new(6, TileColor::Red, false)]);
+
+        let cmd = parse("x0(5)r").expect("should parse");
+        assert!(matches!(
+            command_to_tile_command(&cmd, &game),
+            Err(TileCommandError::NotInRow(0))
+        ));
+
+        let cmd = parse("x1").expect("should parse");
+        let tile_command = command_to_tile_command(&cmd, &game).expect("should validate");
+        assert_eq!(tile_command, TileCommand::Delete { index: 1 });
+        game.operate(tile_command.into()).unwrap();
+
+        // the set at index 2 keeps its index
+        assert!(game.board[1].is_empty());
+        assert_eq!(game.board[2].len(), 3);
+
+        let cmd = parse("x1").expect("should parse");
+        assert!(command_to_tile_command(&cmd, &game).is_err());
+
+        let cmd = parse("x2").expect("should parse");
+        game.operate(command_to_tile_command(&cmd, &game).unwrap().into())
+            .unwrap();
+        assert_eq!(game.board.len(), 1);
+
+        for input in ["x0", "x5"] {
+            let cmd = parse(input).expect("should parse");
+            assert!(command_to_tile_command(&cmd, &game).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_split_and_merge() {
+        let run = |numbers: &[u8]| {
+            numbers
+                .iter()
+                .map(|&n| Tile::new(n, TileColor::Orange, false))
+                .collect::<Vec<_>>()
+        };
+        let mut game =
+            Game::new_with_board(vec![vec![], run(&[1, 2, 3, 4, 5, 6]), run(&[8, 9, 10])]);
+
+        let cmd = parse("s1@3").expect("should parse");
+        assert_eq!(cmd.position, Some(3));
+        let tile_command = command_to_tile_command(&cmd, &game).expect("should validate");
+        game.operate(tile_command.into()).unwrap();
+        assert_eq!(game.board[1], run(&[1, 2, 3]));
+        assert_eq!(game.board[3], run(&[4, 5, 6]));
+
+        // merging 4,5,6 with 8,9,10 would leave a gap
+        let cmd = parse("j3>2").expect("should parse");
+        assert!(matches!(
+            command_to_tile_command(&cmd, &game),
+            Err(TileCommandError::InvalidSet(2))
+        ));
+
+        let cmd = parse("j3>1").expect("should parse");
+        let tile_command = command_to_tile_command(&cmd, &game).expect("should validate");
+        game.operate(tile_command.into()).unwrap();
+        assert_eq!(game.board.len(), 3);
+        assert_eq!(game.board[1], run(&[1, 2, 3, 4, 5, 6]));
+
+        // 1,2 and 3,4,5,6 are not both valid sets
+        let cmd = parse("s1@2").expect("should parse");
+        assert!(command_to_tile_command(&cmd, &game).is_err());
+
+        for input in ["s1@0", "s1@6", "j1>1", "j1>0", "s0@1"] {
+            let cmd = parse(input).expect("should parse");
+            assert!(command_to_tile_command(&cmd, &game).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_command_sequence() {
+        let mut game = Game::new();
+        game.board[0] = vec![
+            Tile::new(3, TileColor::Red, false),
+            Tile::new(4, TileColor::Red, false),
+            Tile::new(5, TileColor::Red, false),
+            Tile::new(6, TileColor::Red, false),
+        ];
+
+        // the second command targets the set created by the first one
+        let operations = command_sequence_to_operations("f(3,4,5)r; x0(6)r ;a1(6)r;", &game)
+            .expect("should validate");
+        assert_eq!(operations.len(), 3);
+
+        for operation in operations {
+            game.operate(operation).unwrap();
+        }
+        assert!(game.board[0].is_empty());
+        assert_eq!(game.board[1].len(), 4);
+
+        let result = command_sequence_to_operations("p(1,2,3)b;a5(4)b;p(r,b,h)9", &game);
+        match result {
+            Err(TileCommandError::Sequence {
+                position, command, ..
+            }) => {
+                assert_eq!(position, 2);
+                assert_eq!(command, "a5(4)b");
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        assert!(matches!(
+            command_sequence_to_operations("a9(4)b", &game),
+            Err(TileCommandError::InvalidIndex)
+        ));
+        assert!(command_sequence_to_operations(" ; ", &game).is_err());
+
+        // syntax errors point into the whole line
+        match command_sequence_to_operations("p(1,2,3)b; a1(4)x", &game) {
+            Err(TileCommandError::Syntax(errors)) => assert_eq!(errors[0].column, 16),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_player() {
+        let cmd = parse_player("join Alice").expect("should parse");
+        assert_eq!(
+            cmd.as_player_command().unwrap(),
+            PlayerCommand::Join {
+                name: "Alice".to_string(),
+                tile_count: 14
+            }
+        );
+
+        let cmd = parse_player("played 2 3").expect("should parse");
+        assert_eq!(
+            cmd.as_player_command().unwrap(),
+            PlayerCommand::Played {
+                player: 2,
+                count: 3
+            }
+        );
+
+        let cmd = parse_player("took 1 r(3)b(w)").expect("should parse");
+        match cmd.as_player_command().unwrap() {
+            PlayerCommand::PickedUp { player, tiles } => {
+                assert_eq!(player, 1);
+                assert_eq!(tiles.len(), 2);
+                assert!(tiles[1].is_wildcard);
+            }
+            other => panic!("unexpected command {:?}", other),
+        }
+
+        let cmd = parse_player("took 1 r(3)b(x)").expect("should parse");
+        match cmd.as_player_command() {
+            Err(TileCommandError::Syntax(errors)) => assert_eq!(errors[0].column, 13),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        assert!(parse_player("next").is_some());
+        assert!(parse_player("meld x").unwrap().as_player_command().is_err());
+        assert!(parse_player("a0(3)r").is_none());
+
+        assert_eq!(
+            TileCommandError::InvalidCommand.to_string(),
+            "Invalid command, please key in 'a'/'p'/'d'/'r'/'f'/'m'/'x'/'s'/'j' \
+             or join/next/drew/played/took/meld only!"
+        );
     }
 
     #[test]
     fn test_parse_init() {
-        let p = Parser::new();
         let input = "r(1)b(2)h(3)";
-        let commands = p.parse_init(input).expect("should parse init commands");
-        let commands = commands
-            .iter()
-            .map(|cmd| cmd.as_tile_command())
-            .collect::<Vec<_>>();
+        let commands = parse_init(input).expect("should parse init commands");
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[2].span, 8..12);
+
+        let commands = commands_to_tile_commands(&commands, &Game::new()).expect("should validate");
         println!("{:?}", commands);
     }
 
     #[test]
     fn test_parse_init2() {
-        let p = Parser::new();
         let input = "r(1)b(2)h(3)o(w)";
-        let commands_init = p.parse_init(input).expect("should parse init commands");
-        let commands = commands_init
-            .iter()
-            .map(|cmd| cmd.as_tile_command())
-            .collect::<Vec<_>>();
-
-        println!("{:?}", commands);
+        let commands = parse_init(input).expect("should parse init commands");
 
         let tiles = commands
-            .into_iter()
-            .map(|cmd| cmd.unwrap().to_tiles())
+            .iter()
+            .map(|cmd| cmd.to_tiles().unwrap())
             .collect::<Vec<_>>();
 
+        assert!(tiles[3][0].is_wildcard);
         println!("{:?}", tiles);
     }
+
+    #[test]
+    fn test_parse_init_lenient() {
+        let expected = parse_init("r(3)").unwrap()[0].tiles.clone();
+
+        for input in ["r(3)", "R (3)", "red 3", "RED(3)", "r3"] {
+            let commands = parse_init(input).expect(input);
+            assert_eq!(commands.len(), 1);
+            assert_eq!(commands[0].tiles, expected, "{}", input);
+        }
+
+        let commands = parse_init("red 3 Blue(4) orange w h 13").expect("should parse");
+        assert_eq!(commands.len(), 4);
+        assert_eq!(
+            commands[2].tiles,
+            list(vec![Arg::Wildcard], Tail::Color(TileColor::Orange))
+        );
+    }
+
+    #[test]
+    fn test_parse_init_free_form() {
+        let tiles = |input: &str| {
+            parse_init(input)
+                .expect(input)
+                .iter()
+                .flat_map(|cmd| cmd.to_tiles().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let hand = tiles("r3 r4 b10 h13 w");
+        assert_eq!(hand.len(), 5);
+        assert_eq!(hand[2], Tile::new(10, TileColor::Blue, false));
+        assert_eq!(hand[3], Tile::new(13, TileColor::Black, false));
+        assert!(hand[4].is_wildcard);
+
+        assert_eq!(tiles("r:3,4,5 b:10"), tiles("r3 r4 r5 b10"));
+        assert_eq!(tiles("R: 3-5, w"), tiles("r3 r4 r5 w"));
+    }
+
+    #[test]
+    fn test_parse_init_errors() {
+        // unrecognized text is reported instead of skipped
+        let errors = parse_init("r(1) x(2)").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            (errors[0].column, errors[0].expected.as_str()),
+            (5, HAND_TILE)
+        );
+
+        let errors = parse_init("r(1)b(0)").unwrap_err();
+        assert_eq!(errors[0].column, 6);
+
+        // every invalid token is reported, not just the first
+        let errors = parse_init("r3 x5 b0 h4 b:2,14").unwrap_err();
+        let columns = errors.iter().map(|e| e.column).collect::<Vec<_>>();
+        assert_eq!(columns, vec![3, 7, 16]);
+
+        assert!(parse_init("").is_err());
+    }
+
+    #[test]
+    fn test_parse_hand() {
+        let commands = parse_hand("hand r3 b:10,11")
+            .unwrap()
+            .expect("should parse");
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].idx, Some(0));
+
+        // columns point into the whole input line
+        let errors = parse_hand("  hand r3 x").unwrap().unwrap_err();
+        assert_eq!(errors[0].column, 10);
+
+        assert!(parse_hand("hand").unwrap().is_err());
+        assert!(parse_hand("handle").is_none());
+        assert!(parse_hand("a0(3)r").is_none());
+    }
 }
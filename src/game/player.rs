@@ -0,0 +1,275 @@
+use super::tile::Tile;
+use super::Game;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{self, Display};
+
+/// Number of tiles in a Rummikub set, no player can hold more.
+pub const TILE_POOL: usize = 106;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Player {
+    pub name: String,
+    pub tile_count: usize,
+    pub has_initial_meld: bool,
+    pub known_tiles: Vec<Tile>,
+}
+
+impl Player {
+    pub fn new(name: &str, tile_count: usize) -> Self {
+        Player {
+            name: name.to_string(),
+            tile_count,
+            has_initial_meld: false,
+            known_tiles: Vec::new(),
+        }
+    }
+
+    // counts come straight from the user, so they are capped at the tile pool
+    fn add_tiles(&mut self, count: usize) -> Result<(), PlayerError> {
+        match self.tile_count.checked_add(count) {
+            Some(tile_count) if tile_count <= TILE_POOL => {
+                self.tile_count = tile_count;
+                Ok(())
+            }
+            _ => Err(PlayerError::TooManyTiles {
+                name: self.name.clone(),
+                tile_count: self.tile_count.saturating_add(count),
+            }),
+        }
+    }
+
+    // player 0 is always the user, whose tiles are tracked in board[0]
+    pub fn default_players() -> Vec<Player> {
+        vec![Player::new("You", 0)]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlayerCommand {
+    Join { name: String, tile_count: usize },
+    NextTurn,
+    Drew { player: usize, count: usize },
+    Played { player: usize, count: usize },
+    PickedUp { player: usize, tiles: Vec<Tile> },
+    Meld { player: usize },
+}
+
+/// A player command that does not fit the players of the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayerError {
+    NoSuchPlayer(usize),
+    NotAnOpponent,
+    NotEnoughTiles { name: String, tile_count: usize },
+    TooManyTiles { name: String, tile_count: usize },
+}
+
+impl Display for PlayerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayerError::NoSuchPlayer(player) => write!(f, "There is no player {}!", player),
+            PlayerError::NotAnOpponent => {
+                write!(f, "Player 0 is you, use 'a'/'d' to change your own tiles!")
+            }
+            PlayerError::NotEnoughTiles { name, tile_count } => {
+                write!(f, "{} only holds {} tiles!", name, tile_count)
+            }
+            PlayerError::TooManyTiles { name, tile_count } => write!(
+                f,
+                "{} cannot hold {} tiles, there are only {} in the game!",
+                name, tile_count, TILE_POOL
+            ),
+        }
+    }
+}
+
+impl Error for PlayerError {}
+
+impl Game {
+    /// The player whose turn it is, `None` if `current_turn` or `players` were edited
+    /// into an inconsistent state.
+    pub fn current_player(&self) -> Option<&Player> {
+        self.players.get(self.current_turn)
+    }
+
+    /// Number of tiles a player holds, the user's count comes from `board[0]`.
+    pub fn tile_count(&self, player: usize) -> Option<usize> {
+        if player == 0 {
+            self.board.first().map(Vec::len)
+        } else {
+            self.players.get(player).map(|p| p.tile_count)
+        }
+    }
+
+    pub fn apply_player_command(&mut self, command: PlayerCommand) -> Result<(), PlayerError> {
+        match command {
+            PlayerCommand::Join { name, tile_count } => {
+                if tile_count > TILE_POOL {
+                    return Err(PlayerError::TooManyTiles { name, tile_count });
+                }
+
+                self.players.push(Player::new(&name, tile_count));
+            }
+
+            PlayerCommand::NextTurn => {
+                // an edited game without players has no turn to pass
+                if self.players.is_empty() {
+                    return Err(PlayerError::NoSuchPlayer(0));
+                }
+
+                self.current_turn = (self.current_turn + 1) % self.players.len();
+            }
+
+            PlayerCommand::Drew { player, count } => {
+                self.validate_opponent(player)?.add_tiles(count)?;
+            }
+
+            PlayerCommand::Played { player, count } => {
+                let opponent = self.validate_opponent(player)?;
+
+                if count > opponent.tile_count {
+                    return Err(PlayerError::NotEnoughTiles {
+                        name: opponent.name.clone(),
+                        tile_count: opponent.tile_count,
+                    });
+                }
+
+                opponent.tile_count -= count;
+            }
+
+            PlayerCommand::PickedUp { player, tiles } => {
+                let opponent = self.validate_opponent(player)?;
+
+                opponent.add_tiles(tiles.len())?;
+                opponent.known_tiles.extend(tiles);
+                opponent.known_tiles.sort_unstable();
+            }
+
+            PlayerCommand::Meld { player } => {
+                self.players
+                    .get_mut(player)
+                    .ok_or(PlayerError::NoSuchPlayer(player))?
+                    .has_initial_meld = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_opponent(&mut self, player: usize) -> Result<&mut Player, PlayerError> {
+        if player == 0 {
+            return Err(PlayerError::NotAnOpponent);
+        }
+
+        self.players
+            .get_mut(player)
+            .ok_or(PlayerError::NoSuchPlayer(player))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tile_color::TileColor;
+
+    #[test]
+    fn test_turns_and_counts() {
+        let mut game = Game::new();
+
+        game.apply_player_command(PlayerCommand::Join {
+            name: "Alice".to_string(),
+            tile_count: 14,
+        })
+        .unwrap();
+        game.apply_player_command(PlayerCommand::Join {
+            name: "Bob".to_string(),
+            tile_count: 14,
+        })
+        .unwrap();
+
+        assert_eq!(game.current_player().unwrap().name, "You");
+        game.apply_player_command(PlayerCommand::NextTurn).unwrap();
+        assert_eq!(game.current_player().unwrap().name, "Alice");
+
+        game.apply_player_command(PlayerCommand::Played {
+            player: 1,
+            count: 3,
+        })
+        .unwrap();
+        game.apply_player_command(PlayerCommand::Meld { player: 1 })
+            .unwrap();
+        game.apply_player_command(PlayerCommand::Drew {
+            player: 2,
+            count: 1,
+        })
+        .unwrap();
+        game.apply_player_command(PlayerCommand::PickedUp {
+            player: 2,
+            tiles: vec![Tile::new(5, TileColor::Blue, false)],
+        })
+        .unwrap();
+
+        assert_eq!(game.tile_count(1), Some(11));
+        assert!(game.players[1].has_initial_meld);
+        assert_eq!(game.tile_count(2), Some(16));
+        assert_eq!(game.tile_count(3), None);
+        assert_eq!(game.players[2].known_tiles.len(), 1);
+
+        game.apply_player_command(PlayerCommand::NextTurn).unwrap();
+        game.apply_player_command(PlayerCommand::NextTurn).unwrap();
+        assert_eq!(game.current_turn, 0);
+    }
+
+    #[test]
+    fn test_invalid_player() {
+        let mut game = Game::new();
+
+        assert_eq!(
+            game.apply_player_command(PlayerCommand::Drew {
+                player: 0,
+                count: 1
+            }),
+            Err(PlayerError::NotAnOpponent)
+        );
+        assert_eq!(
+            game.apply_player_command(PlayerCommand::Meld { player: 3 }),
+            Err(PlayerError::NoSuchPlayer(3))
+        );
+
+        game.apply_player_command(PlayerCommand::Join {
+            name: "Alice".to_string(),
+            tile_count: 2,
+        })
+        .unwrap();
+
+        assert!(game
+            .apply_player_command(PlayerCommand::Played {
+                player: 1,
+                count: 3
+            })
+            .is_err());
+
+        assert_eq!(
+            game.apply_player_command(PlayerCommand::Drew {
+                player: 1,
+                count: usize::MAX
+            }),
+            Err(PlayerError::TooManyTiles {
+                name: "Alice".to_string(),
+                tile_count: usize::MAX
+            })
+        );
+        assert_eq!(game.tile_count(1), Some(2));
+        assert!(game
+            .apply_player_command(PlayerCommand::Join {
+                name: "Bob".to_string(),
+                tile_count: TILE_POOL + 1
+            })
+            .is_err());
+
+        // a game edited down to no players has nobody to pass the turn to
+        game.players.clear();
+        assert!(game.current_player().is_none());
+        assert!(game.apply_player_command(PlayerCommand::NextTurn).is_err());
+    }
+}
@@ -1,17 +1,27 @@
 use super::tile_color::TileColor;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::fmt;
 
-#[derive(Debug, Clone, Hash)]
+/// A tile with its face value `1`-`13`, a joker without a value has the number 251.
+// `PartialEq` compares every field, so the derived `Hash` agrees with it
+#[allow(clippy::derived_hash_with_manual_eq)]
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
 pub struct Tile {
     pub number: u8,
     pub color: TileColor,
     pub is_wildcard: bool,
+    // a joker whose value was given by the user, e.g. `w7`, rather than guessed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_pinned: bool,
 }
 
 impl PartialEq for Tile {
     fn eq(&self, other: &Self) -> bool {
-        self.number == other.number && self.color == other.color && self.is_wildcard == other.is_wildcard
+        self.number == other.number
+            && self.color == other.color
+            && self.is_wildcard == other.is_wildcard
+            && self.is_pinned == other.is_pinned
     }
 }
 
@@ -20,7 +30,11 @@ impl Eq for Tile {}
 impl Ord for Tile {
     fn cmp(&self, other: &Self) -> Ordering {
         match self.color.cmp(&other.color) {
-            Ordering::Equal => self.number.cmp(&other.number),
+            Ordering::Equal => self
+                .number
+                .cmp(&other.number)
+                .then(self.is_wildcard.cmp(&other.is_wildcard))
+                .then(self.is_pinned.cmp(&other.is_pinned)),
             ord => ord,
         }
     }
@@ -32,20 +46,53 @@ impl PartialOrd for Tile {
     }
 }
 
+/// The tile in the notation of a hand: `r3`, `w` for a joker, `r(w7)` for a joker pinned
+/// to red 7 and `r(w~7)` for a free joker last placed as red 7.
 impl fmt::Display for Tile {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let is_wildcard = if self.is_wildcard { "w" } else { "n" };
-
-        write!(f, "{}({}){}", self.color, self.number, is_wildcard)
+        if self.is_pinned {
+            write!(f, "{}(w{})", self.color, self.number)
+        } else if self.is_wildcard && (1..=13).contains(&self.number) {
+            write!(f, "{}(w~{})", self.color, self.number)
+        } else if self.is_wildcard {
+            write!(f, "w")
+        } else {
+            write!(f, "{}{}", self.color, self.number)
+        }
     }
 }
 
 impl Tile {
-    pub(crate) fn new(number: u8, color: TileColor, is_wildcard: bool) -> Self {
+    pub fn new(number: u8, color: TileColor, is_wildcard: bool) -> Self {
         Tile {
             number,
             color,
             is_wildcard,
+            is_pinned: false,
+        }
+    }
+
+    /// A joker standing in for the given tile, which the solver may not reassign.
+    pub fn new_pinned(number: u8, color: TileColor) -> Self {
+        Tile {
+            number,
+            color,
+            is_wildcard: true,
+            is_pinned: true,
+        }
+    }
+
+    /// A joker whose value may still be chosen freely.
+    pub fn is_free_wildcard(&self) -> bool {
+        self.is_wildcard && !self.is_pinned
+    }
+
+    /// Whether both are the same physical tile, jokers match regardless of their assigned value.
+    pub fn is_same_tile(&self, other: &Tile) -> bool {
+        if self.is_wildcard || other.is_wildcard {
+            self.is_wildcard == other.is_wildcard
+        } else {
+            self == other
         }
     }
 
@@ -1,9 +1,10 @@
-use crossterm::style::Color;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::fmt;
 
 // h, b, o, r
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TileColor {
     Black,
     Blue,
@@ -11,6 +12,7 @@ pub enum TileColor {
     Red,
 }
 
+#[allow(clippy::non_canonical_partial_ord_impl)]
 impl PartialOrd for TileColor {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         self.to_rank().partial_cmp(&other.to_rank())
@@ -37,25 +39,13 @@ impl fmt::Display for TileColor {
 }
 
 impl TileColor {
-    pub fn as_color(&self) -> Color {
-        match self {
-            TileColor::Blue => Color::Blue,
-            TileColor::Red => Color::Red,
-            TileColor::Orange => Color::Rgb {
-                r: 232,
-                g: 118,
-                b: 0,
-            },
-            TileColor::Black => Color::Black,
-        }
-    }
-
+    /// Accepts the short form (b/r/o/h) or the full name, in any case.
     pub fn str_to_tile_color(s: &str) -> Option<Self> {
-        match s {
-            "b" => Some(TileColor::Blue),
-            "r" => Some(TileColor::Red),
-            "o" => Some(TileColor::Orange),
-            "h" => Some(TileColor::Black),
+        match s.to_ascii_lowercase().as_str() {
+            "b" | "blue" => Some(TileColor::Blue),
+            "r" | "red" => Some(TileColor::Red),
+            "o" | "orange" => Some(TileColor::Orange),
+            "h" | "black" => Some(TileColor::Black),
             _ => None,
         }
     }
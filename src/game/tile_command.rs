@@ -1,26 +1,45 @@
 use crate::game::tile_color::TileColor;
-use crate::game::{tile::Tile, Command, GameOperation};
+use crate::game::{player::PlayerError, tile::Tile, Command, GameOperation, OperationError};
 
-use super::{Game, ToTiles};
+use super::parser::{command_list, Arg, CommandAst, ParseError, Tail, TileList};
+use super::turn::TurnError;
+use super::Game;
 use std::error::Error;
 use std::fmt::{self, Display};
 
-#[derive(Debug, Clone)]
-pub(crate) enum TileCommandError {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TileCommandError {
     InvalidCommand,
     InvalidIndex,
     InvalidArgs,
-    InvalidTail,
+    NotInRow(usize),
+    InvalidSet(usize),
+    Syntax(Vec<ParseError>),
+    Sequence {
+        position: usize,
+        command: String,
+        error: Box<TileCommandError>,
+    },
+    Operation(OperationError),
+    Player(PlayerError),
+    Turn(TurnError),
     Other(String),
 }
 
 impl Display for TileCommandError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            TileCommandError::InvalidCommand => write!(f, "Invalid command, please key in 'a'/'p'/'d'/'r' only!"),
+            TileCommandError::InvalidCommand => write!(f, "Invalid command, please key in {} only!", command_list()),
             TileCommandError::InvalidIndex => write!(f, "Invalid index!"),
             TileCommandError::InvalidArgs => write!(f, "Invalid args, please key in only 1-13 or the color r/b/h/o only, and split by ',' !"),
-            TileCommandError::InvalidTail => write!(f, "Invalid tail, please key in only 1-13, or the color r/b/h/o only!"),
+            TileCommandError::NotInRow(0) => write!(f, "Your hand (index 0) does not contain these tiles!"),
+            TileCommandError::NotInRow(idx) => write!(f, "The set at index {} does not contain these tiles!", idx),
+            TileCommandError::InvalidSet(idx) => write!(f, "The set at index {} would not be a valid run or group!", idx),
+            TileCommandError::Syntax(errors) => write!(f, "{}", errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(" ")),
+            TileCommandError::Sequence { position, command, error } => write!(f, "Command {} '{}' failed, nothing was applied: {}", position, command, error),
+            TileCommandError::Operation(e) => write!(f, "{}", e),
+            TileCommandError::Player(e) => write!(f, "{}", e),
+            TileCommandError::Turn(e) => write!(f, "{}", e),
             TileCommandError::Other(msg) => write!(f, "{}", msg),
         }
     }
@@ -28,314 +47,396 @@ impl Display for TileCommandError {
 
 impl Error for TileCommandError {}
 
-#[derive(Debug)]
-pub(crate) struct TileCommand {
-    pub cmd: Command,
-    pub idx: usize,
-    pub args: Vec<String>,
-    pub tail: String,
-    pub replace_args: Option<Vec<String>>,
-    pub replace_tail: Option<String>,
+impl From<OperationError> for TileCommandError {
+    fn from(e: OperationError) -> Self {
+        TileCommandError::Operation(e)
+    }
 }
 
-impl TileCommand {
-    pub fn new(
-        cmd: Command,
-        idx: usize,
-        args: Vec<String>,
-        tail: String,
-        replace_args: Option<Vec<String>>,
-        replace_tail: Option<String>,
-    ) -> Self {
-        TileCommand {
-            cmd,
-            idx,
-            args,
-            tail,
-            replace_args,
-            replace_tail,
-        }
+impl From<PlayerError> for TileCommandError {
+    fn from(e: PlayerError) -> Self {
+        TileCommandError::Player(e)
     }
+}
 
-    pub fn validate(&self, game: &Game) -> Result<Self, TileCommandError> {
-        let idx = match self.cmd {
-            Command::Add | Command::Replace => game
-                .validate_index(self.idx)
-                .then_some(self.idx)
-                .ok_or(TileCommandError::InvalidIndex),
-            _ => Ok(self.idx),
-        }?;
-
-        let is_color = Ok(self.tail.chars().all(|ch| ch.is_ascii_alphabetic()))?;
-
-        let args = if is_color {
-            self.validate_numbers(self.args.clone())
-        } else {
-            self.validate_colors(self.args.clone())
-        }?;
-
-        if self.cmd == Command::Replace {
-            let replace_tail = self.replace_tail.clone().ok_or(TileCommandError::Other(
-                "Invalid replace command format!".to_string(),
-            ))?;
-
-            let replace_is_color = Ok(replace_tail.chars().all(|ch| ch.is_ascii_alphabetic()))?;
-
-            let replace_args = self.replace_args.clone().ok_or(TileCommandError::Other(
-                "Invalid replace command format!".to_string(),
-            ))?;
-
-            let replace_args = if replace_is_color {
-                self.validate_numbers(replace_args)
-            } else {
-                self.validate_colors(replace_args)
-            }?;
-
-            Ok(TileCommand::new(
-                self.cmd.clone(),
-                self.idx,
-                self.args.clone(),
-                self.tail.clone(),
-                Some(replace_args),
-                Some(replace_tail),
-            ))
-        } else {
-            Ok(TileCommand::new(
-                self.cmd.clone(),
-                idx,
-                args,
-                self.tail.clone(),
-                None,
-                None,
-            ))
+impl From<TurnError> for TileCommandError {
+    fn from(e: TurnError) -> Self {
+        match e {
+            TurnError::Operation(e) => TileCommandError::Operation(e),
+            e => TileCommandError::Turn(e),
         }
     }
-
-    fn validate_numbers(&self, args: Vec<String>) -> Result<Vec<String>, TileCommandError> {
-        let test_args = args.clone();
-        let n = test_args.len();
-
-        let test_args = test_args
-            .iter()
-            .filter(|s| s == &&"w" || s.parse::<u8>().is_ok())
-            .filter(|s| {
-                let d = if s == &&"w" {
-                    251
-                } else {
-                    s.parse::<u8>().unwrap_or(0)
-                };
-
-                d == 251 || (d >= 1 && d <= 13)
-            })
-            .collect::<Vec<&String>>();
-
-        (test_args.len() == n)
-            .then_some(args.clone())
-            .ok_or(TileCommandError::InvalidArgs)
-    }
-
-    fn validate_colors(&self, args: Vec<String>) -> Result<Vec<String>, TileCommandError> {
-        let test_args = args.clone();
-        let n = test_args.len();
-
-        let test_args = test_args
-            .iter()
-            .filter(|s| s == &&"w" || s == &&"r" || s == &&"b" || s == &&"h" || s == &&"o")
-            .collect::<Vec<&String>>();
-
-        (test_args.len() == n)
-            .then_some(args.clone())
-            .ok_or(TileCommandError::InvalidArgs)
-    }
 }
 
-impl ToTiles for TileCommand {
-    fn to_tiles(&self) -> GameOperation {
-        let cmd = self.cmd.clone();
-        let idx = self.idx;
-        let args = self.args.clone();
-        let tail = self.tail.clone();
-        let tiles_generator = TilesGenerator::new(args);
-
-        let replace_tiles = if cmd == Command::Replace {
-            let replace_tail = self.replace_tail.clone().unwrap();
-            let replace_args = self.replace_args.clone().unwrap();
-            let replace_tiles_generator = TilesGenerator::new(replace_args);
-
-            match replace_tail {
-                c if c.chars().all(|ch| ch.is_ascii_alphabetic()) => {
-                    let color = match c.chars().nth(0).unwrap() {
-                        'r' => TileColor::Red,
-                        'b' => TileColor::Blue,
-                        'h' => TileColor::Black,
-                        'o' => TileColor::Orange,
-                        _ => panic!("Invalid color!"),
-                    };
-
-                    Some(replace_tiles_generator.generate_numbers_tiles(color))
-                }
+/// A command checked against the board, each variant holds exactly what it needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TileCommand {
+    Add {
+        index: usize,
+        tiles: Vec<Tile>,
+    },
+    Put {
+        tiles: Vec<Tile>,
+    },
+    Draw {
+        index: usize,
+        tiles: Vec<Tile>,
+    },
+    /// `replacement` takes the place of the wildcards in the set at `index`, which then
+    /// join `tiles` as a new set.
+    Replace {
+        index: usize,
+        replacement: Vec<Tile>,
+        tiles: Vec<Tile>,
+    },
+    Play {
+        tiles: Vec<Tile>,
+    },
+    Move {
+        from: usize,
+        to: usize,
+        tiles: Vec<Tile>,
+    },
+    Remove {
+        index: usize,
+        tiles: Vec<Tile>,
+    },
+    Delete {
+        index: usize,
+    },
+    Split {
+        index: usize,
+        position: usize,
+    },
+    Merge {
+        from: usize,
+        to: usize,
+    },
+}
 
-                c if c.chars().all(|ch| ch.is_ascii_digit()) => {
-                    let num = c.parse::<u8>().unwrap_or_else(|_| {
-                        panic!("Invalid digit in replace tail: {}", c);
-                    });
+impl TileCommand {
+    /// Check a parsed command against the board and build the tiles it acts on.
+    pub fn validate(ast: &CommandAst, game: &Game) -> Result<Self, TileCommandError> {
+        let index = || ast.idx.ok_or(TileCommandError::InvalidIndex);
+        let target = || ast.target.ok_or(TileCommandError::InvalidIndex);
+        let tiles = || ast.to_tiles();
+        let valid_index = |idx: usize| {
+            game.validate_index(idx)
+                .then_some(idx)
+                .ok_or(TileCommandError::InvalidIndex)
+        };
 
-                    Some(replace_tiles_generator.generate_colors_tiles(num))
+        let command = match ast.cmd {
+            Command::Add => TileCommand::Add {
+                index: valid_index(index()?)?,
+                tiles: tiles()?,
+            },
+            Command::Draw => TileCommand::Draw {
+                index: valid_index(ast.idx.unwrap_or(0))?,
+                tiles: tiles()?,
+            },
+            Command::Put => TileCommand::Put { tiles: tiles()? },
+            Command::Play => TileCommand::Play { tiles: tiles()? },
+            Command::Remove => TileCommand::Remove {
+                index: valid_index(index()?)?,
+                tiles: tiles()?,
+            },
+            Command::Replace => {
+                let replacement = ast.replace.as_ref().ok_or(TileCommandError::Other(
+                    "Invalid replace command format!".to_string(),
+                ))?;
+
+                TileCommand::Replace {
+                    index: valid_index(index()?)?,
+                    replacement: replacement.to_tiles()?,
+                    tiles: tiles()?,
                 }
-
-                _ => Some(Vec::new()),
             }
-        } else {
-            None
-        };
+            Command::Delete => match index()? {
+                0 => return Err(TileCommandError::Other(
+                    "The hand (index 0) cannot be deleted, use x0([args])[tail] to remove tiles!"
+                        .to_string(),
+                )),
+                idx if game.validate_index(idx) && !game.board[idx].is_empty() => {
+                    TileCommand::Delete { index: idx }
+                }
+                _ => return Err(TileCommandError::InvalidIndex),
+            },
+            Command::Split => {
+                let idx = index()?;
+                let position = ast.position.ok_or(TileCommandError::InvalidIndex)?;
+                let is_valid = idx != 0
+                    && game.validate_index(idx)
+                    && position > 0
+                    && position < game.board[idx].len();
+
+                if !is_valid {
+                    return Err(TileCommandError::InvalidIndex);
+                }
 
-        let tiles = match tail {
-            c if c.chars().all(|ch| ch.is_ascii_alphabetic()) => {
-                let color = match c.chars().nth(0).unwrap() {
-                    'r' => TileColor::Red,
-                    'b' => TileColor::Blue,
-                    'h' => TileColor::Black,
-                    'o' => TileColor::Orange,
-                    _ => panic!("Invalid color!"),
-                };
-
-                tiles_generator.generate_numbers_tiles(color)
+                TileCommand::Split {
+                    index: idx,
+                    position,
+                }
             }
+            Command::Move | Command::Merge => {
+                let (from, to) = (index()?, target()?);
+                let is_valid = game.validate_index(from)
+                    && game.validate_index(to)
+                    && to != 0
+                    && to != from
+                    && !(ast.cmd == Command::Merge && from == 0);
+
+                if !is_valid {
+                    return Err(TileCommandError::InvalidIndex);
+                }
 
-            c if c.chars().all(|ch| ch.is_ascii_digit()) => {
-                let num = c.parse::<u8>().unwrap_or_else(|_| {
-                    panic!("Invalid digit in tail: {}", c);
-                });
-
-                tiles_generator.generate_colors_tiles(num)
+                if ast.cmd == Command::Move {
+                    TileCommand::Move {
+                        from,
+                        to,
+                        tiles: tiles()?,
+                    }
+                } else {
+                    TileCommand::Merge { from, to }
+                }
             }
+        };
 
-            _ => Vec::new(),
+        let source = match &command {
+            TileCommand::Play { tiles } => Some((0, tiles)),
+            TileCommand::Move { from, tiles, .. } => Some((*from, tiles)),
+            TileCommand::Remove { index, tiles } => Some((*index, tiles)),
+            _ => None,
         };
 
-        GameOperation::new(cmd, idx, tiles, replace_tiles)
-    }
-}
+        if let Some((source, tiles)) = source {
+            if !game.row_contains(source, tiles) {
+                return Err(TileCommandError::NotInRow(source));
+            }
+        }
 
-struct TilesGenerator {
-    args: Vec<String>,
-}
+        if matches!(
+            command,
+            TileCommand::Split { .. } | TileCommand::Merge { .. } | TileCommand::Move { .. }
+        ) {
+            command.validate_result(game)?;
+        }
 
-impl TilesGenerator {
-    fn new(args: Vec<String>) -> Self {
-        TilesGenerator { args }
+        Ok(command)
     }
 
-    fn generate_numbers_tiles(&self, color: TileColor) -> Vec<Tile> {
-        let mut tiles = Vec::new();
+    // apply the command to a copy of the game and check every set it touched
+    fn validate_result(&self, game: &Game) -> Result<(), TileCommandError> {
+        let mut result = game.clone();
+        let rows = result.board.len();
+        result.operate(self.clone().into())?;
 
-        for arg in &self.args {
-            let num = arg.parse::<u8>().unwrap_or_else(|_| {
-                (arg == "w").then_some(251).unwrap_or_else(|| {
-                    panic!("Invalid number in args: {}", arg);
-                })
-            });
+        let mut touched = match *self {
+            TileCommand::Merge { from, to } | TileCommand::Move { from, to, .. } => {
+                vec![from, to]
+            }
+            TileCommand::Split { index, .. } => vec![index],
+            _ => Vec::new(),
+        };
+        touched.extend(rows..result.board.len());
 
-            let is_wildcard = arg == "w";
-            let num = is_wildcard.then_some(251).unwrap_or(num);
-            let color = is_wildcard.then_some(TileColor::Red).unwrap_or(color);
+        // the hand is never a set
+        for idx in touched.into_iter().filter(|&idx| idx != 0) {
+            let tiles = result.board.get(idx).cloned().unwrap_or_default();
 
-            tiles.push(Tile::new(num, color, is_wildcard));
+            if !tiles.is_empty() && !Game::is_valid_set(&tiles) {
+                return Err(TileCommandError::InvalidSet(idx));
+            }
         }
 
-        tiles
+        Ok(())
     }
+}
 
-    fn generate_colors_tiles(&self, num: u8) -> Vec<Tile> {
-        let mut tiles = Vec::new();
-
-        for arg in &self.args {
-            let color = match arg.as_str() {
-                "r" => TileColor::Red,
-                "b" => TileColor::Blue,
-                "h" => TileColor::Black,
-                "o" => TileColor::Orange,
-                "w" => TileColor::Red,
-                _ => panic!("Invalid color in args: {}", arg),
-            };
-
-            let is_wildcard = arg == "w";
-            let num = is_wildcard.then_some(251).unwrap_or(num);
-            let color = is_wildcard.then_some(TileColor::Red).unwrap_or(color);
-
-            tiles.push(Tile::new(num, color, is_wildcard));
+impl From<TileCommand> for GameOperation {
+    fn from(command: TileCommand) -> Self {
+        match command {
+            TileCommand::Add { index, tiles } => {
+                GameOperation::new(Command::Add, index, tiles, None)
+            }
+            TileCommand::Put { tiles } => GameOperation::new(Command::Put, usize::MAX, tiles, None),
+            TileCommand::Draw { index, tiles } => {
+                GameOperation::new(Command::Draw, index, tiles, None)
+            }
+            TileCommand::Replace {
+                index,
+                replacement,
+                tiles,
+            } => GameOperation::new(Command::Replace, index, tiles, Some(replacement)),
+            TileCommand::Play { tiles } => {
+                GameOperation::new(Command::Play, usize::MAX, tiles, None)
+            }
+            TileCommand::Move { from, to, tiles } => GameOperation::new_move(from, to, tiles),
+            TileCommand::Remove { index, tiles } => {
+                GameOperation::new(Command::Remove, index, tiles, None)
+            }
+            TileCommand::Delete { index } => {
+                GameOperation::new(Command::Delete, index, Vec::new(), None)
+            }
+            TileCommand::Split { index, position } => GameOperation::new_split(index, position),
+            TileCommand::Merge { from, to } => GameOperation::new_merge(from, to),
         }
+    }
+}
 
-        tiles
+impl TileList {
+    /// The tiles of the list, a run for a color tail and a group for a number tail.
+    pub fn to_tiles(&self) -> Result<Vec<Tile>, TileCommandError> {
+        self.args
+            .iter()
+            .map(|arg| match (*arg, self.tail) {
+                (Arg::Wildcard, _) => Ok(Tile::new(251, TileColor::Red, true)),
+                (Arg::Number(number), Tail::Color(color)) if (1..=13).contains(&number) => {
+                    Ok(Tile::new(number, color, false))
+                }
+                // `w7` is a joker pinned to 7
+                (Arg::JokerNumber(number), Tail::Color(color)) if (1..=13).contains(&number) => {
+                    Ok(Tile::new_pinned(number, color))
+                }
+                // `w~7` is a free joker, its number is only the last guess
+                (Arg::JokerGuess(number), Tail::Color(color)) if (1..=13).contains(&number) => {
+                    Ok(Tile::new(number, color, true))
+                }
+                (Arg::Color(color), Tail::Number(number)) if (1..=13).contains(&number) => {
+                    Ok(Tile::new(number, color, false))
+                }
+                // `wr` is a joker pinned to red
+                (Arg::JokerColor(color), Tail::Number(number)) if (1..=13).contains(&number) => {
+                    Ok(Tile::new_pinned(number, color))
+                }
+                _ => Err(TileCommandError::InvalidArgs),
+            })
+            .collect()
     }
 }
 
+#[cfg(test)]
 mod tests {
-    #[allow(unused_imports)]
     use super::*;
+    use crate::game::parser::Parser;
+
+    fn validate(input: &str, game: &Game) -> Result<TileCommand, TileCommandError> {
+        let commands = Parser::new(input).parse_sequence().expect("should parse");
+        TileCommand::validate(&commands[0], game)
+    }
 
     #[test]
     fn test1() {
         let game = Game::new();
-        let command = TileCommand {
-            cmd: Command::Put,
-            idx: usize::MAX,
-            replace_args: None,
-            replace_tail: None,
-            args: vec![String::from("10"), String::from("11"), String::from("12")],
-            tail: "r".to_string(),
-        };
-
-        assert!(command.validate(&game).is_ok());
+        let command = validate("p(10,11,12)r", &game).expect("should validate");
+
+        assert_eq!(
+            command,
+            TileCommand::Put {
+                tiles: vec![
+                    Tile::new(10, TileColor::Red, false),
+                    Tile::new(11, TileColor::Red, false),
+                    Tile::new(12, TileColor::Red, false),
+                ]
+            }
+        );
     }
 
     #[test]
     fn test2() {
         let game = Game::new();
-        let command = TileCommand {
-            cmd: Command::Add,
-            idx: 0,
-            replace_args: None,
-            replace_tail: None,
-            args: vec![
-                String::from("r"),
-                String::from("b"),
-                String::from("h"),
-                String::from("o"),
-            ],
-            tail: "10".to_string(),
-        };
+        let command = validate("a0(r,b,h,o)10", &game).expect("should validate");
 
-        assert!(command.validate(&game).is_ok());
+        match command {
+            TileCommand::Add { index: 0, tiles } => assert_eq!(tiles.len(), 4),
+            other => panic!("unexpected command {:?}", other),
+        }
     }
 
     #[test]
     fn test3() {
         let game = Game::new();
-        let command = TileCommand {
-            cmd: Command::Replace,
-            idx: 0,
-            replace_args: Some(vec!["1".to_string()]),
-            replace_tail: Some("h".to_string()),
-            args: vec!["h".to_string()],
-            tail: "10".to_string(),
-        };
-
-        assert!(command.validate(&game).is_ok());
+        let command = validate("r0(1)h(h)10", &game).expect("should validate");
+
+        assert_eq!(
+            command,
+            TileCommand::Replace {
+                index: 0,
+                replacement: vec![Tile::new(1, TileColor::Black, false)],
+                tiles: vec![Tile::new(10, TileColor::Black, false)],
+            }
+        );
     }
 
     #[test]
     fn test4() {
         let game = Game::new();
-        let command = TileCommand {
-            cmd: Command::Replace,
-            idx: 0,
-            replace_args: None,
-            replace_tail: None,
-            args: vec!["h".to_string()],
-            tail: "10".to_string(),
-        };
+        let mut ast = Parser::new("r0(1)h(h)10")
+            .parse_sequence()
+            .unwrap()
+            .remove(0);
+        ast.replace = None;
+
+        assert!(TileCommand::validate(&ast, &game).is_err());
+    }
+
+    #[test]
+    fn test_mismatched_args() {
+        // the parser never produces these, but conversion must not panic on them
+        let list = |args: Vec<Arg>, tail: Tail| TileList { args, tail };
+
+        assert!(list(
+            vec![Arg::Color(TileColor::Red)],
+            Tail::Color(TileColor::Red)
+        )
+        .to_tiles()
+        .is_err());
+        assert!(list(vec![Arg::Number(3)], Tail::Number(3))
+            .to_tiles()
+            .is_err());
+        assert!(list(vec![Arg::Number(0)], Tail::Color(TileColor::Red))
+            .to_tiles()
+            .is_err());
+        assert!(list(vec![Arg::Color(TileColor::Red)], Tail::Number(14))
+            .to_tiles()
+            .is_err());
+    }
+
+    #[test]
+    fn test_operate_errors() {
+        let mut game = Game::new();
+        let board = game.board.clone();
+
+        let mut operation = GameOperation::new(Command::Replace, 0, Vec::new(), None);
+        assert_eq!(
+            game.operate(operation.clone()),
+            Err(OperationError::MissingReplaceTiles)
+        );
+
+        operation.replace_tiles = Some(vec![Tile::new(1, TileColor::Red, false)]);
+        assert!(matches!(
+            game.operate(operation),
+            Err(OperationError::WildcardCount { wildcards: 0, .. })
+        ));
+
+        for command in [
+            Command::Add,
+            Command::Draw,
+            Command::Remove,
+            Command::Delete,
+        ] {
+            let operation = GameOperation::new(command, 5, Vec::new(), None);
+            assert_eq!(
+                game.operate(operation),
+                Err(OperationError::InvalidIndex(5))
+            );
+        }
 
-        assert!(command.validate(&game).is_err());
+        assert_eq!(
+            game.operate(GameOperation::new_move(0, 3, Vec::new())),
+            Err(OperationError::InvalidIndex(3))
+        );
+        assert_eq!(game.board, board);
     }
 }
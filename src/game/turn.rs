@@ -0,0 +1,332 @@
+use super::tile::Tile;
+use super::{Game, GameOperation, OperationError};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TurnError {
+    NotStarted,
+    AlreadyStarted,
+    InvalidSet { index: usize },
+    TileToHand(Tile),
+    TileLost(Tile),
+    TileAdded(Tile),
+    Operation(OperationError),
+}
+
+impl Display for TurnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TurnError::NotStarted => write!(f, "No turn in progress, please 'begin' a turn first!"),
+            TurnError::AlreadyStarted => write!(f, "A turn is already in progress!"),
+            TurnError::InvalidSet { index } => write!(
+                f,
+                "Set at index {} is not a valid run or group, the turn was rolled back!",
+                index
+            ),
+            TurnError::TileToHand(tile) => write!(
+                f,
+                "Tile {} was moved from the table to the hand, the turn was rolled back!",
+                tile
+            ),
+            TurnError::TileLost(tile) => write!(
+                f,
+                "Tile {} is neither on the table nor in the hand anymore, the turn was rolled back!",
+                tile
+            ),
+            TurnError::TileAdded(tile) => write!(
+                f,
+                "Tile {} came from neither the hand nor the table, the turn was rolled back!",
+                tile
+            ),
+            TurnError::Operation(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for TurnError {}
+
+impl Game {
+    pub fn is_turn_started(&self) -> bool {
+        self.turn_snapshot.is_some()
+    }
+
+    /// Snapshot the game so the following operations can be committed or rolled back together.
+    pub fn begin_turn(&mut self) -> Result<(), TurnError> {
+        if self.is_turn_started() {
+            return Err(TurnError::AlreadyStarted);
+        }
+
+        self.turn_snapshot = Some(Box::new(self.clone()));
+
+        Ok(())
+    }
+
+    /// Apply an operation inside the current turn, the table may be invalid until commit.
+    pub fn stage(&mut self, operation: GameOperation) -> Result<(), TurnError> {
+        if !self.is_turn_started() {
+            return Err(TurnError::NotStarted);
+        }
+
+        self.operate(operation).map_err(TurnError::Operation)
+    }
+
+    /// End the turn, rolling back every staged operation if the table is not valid.
+    pub fn commit_turn(&mut self) -> Result<(), TurnError> {
+        let snapshot = self.turn_snapshot.take().ok_or(TurnError::NotStarted)?;
+
+        if let Err(e) = self.validate_turn(&snapshot) {
+            *self = *snapshot;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn rollback_turn(&mut self) -> Result<(), TurnError> {
+        let snapshot = self.turn_snapshot.take().ok_or(TurnError::NotStarted)?;
+        *self = *snapshot;
+
+        Ok(())
+    }
+
+    fn validate_turn(&self, snapshot: &Game) -> Result<(), TurnError> {
+        for (index, tiles) in self.board.iter().enumerate().skip(1) {
+            if !tiles.is_empty() && !Self::is_valid_set(tiles) {
+                return Err(TurnError::InvalidSet { index });
+            }
+        }
+
+        // tiles may only flow from the hand to the table
+        let mut tiles = Self::tile_counts(&snapshot.board[..1]);
+
+        for tile in &self.board[0] {
+            if !Self::take_tile(&mut tiles, tile) {
+                return Err(TurnError::TileToHand(tile.clone()));
+            }
+        }
+
+        // what is left of the hand was played, the table holds exactly those and its own tiles
+        for (key, (count, tile)) in Self::tile_counts(&snapshot.board[1..]) {
+            tiles.entry(key).or_insert((0, tile)).0 += count;
+        }
+
+        for tile in self.board.iter().skip(1).flatten() {
+            if !Self::take_tile(&mut tiles, tile) {
+                return Err(TurnError::TileAdded(tile.clone()));
+            }
+        }
+
+        match tiles.into_values().find(|(count, _)| *count > 0) {
+            Some((_, tile)) => Err(TurnError::TileLost(tile.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn tile_counts(rows: &[Vec<Tile>]) -> HashMap<(u8, u8, bool), (usize, &Tile)> {
+        let mut counts = HashMap::new();
+
+        for tile in rows.iter().flatten() {
+            counts.entry(Self::tile_key(tile)).or_insert((0, tile)).0 += 1;
+        }
+
+        counts
+    }
+
+    // remove one of the tile from the counts, false if there is none left
+    fn take_tile(counts: &mut HashMap<(u8, u8, bool), (usize, &Tile)>, tile: &Tile) -> bool {
+        match counts.get_mut(&Self::tile_key(tile)) {
+            Some((count, _)) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // jokers are interchangeable no matter which value they are assigned
+    fn tile_key(tile: &Tile) -> (u8, u8, bool) {
+        if tile.is_wildcard {
+            (0, 0, true)
+        } else {
+            (tile.number, tile.color.to_rank() as u8, false)
+        }
+    }
+
+    /// Check that the tiles form a run or a group, jokers may stand in for any tile. A pinned
+    /// joker stands in for exactly its tile, so it is checked like one.
+    pub fn is_valid_set(tiles: &[Tile]) -> bool {
+        let n = tiles.len();
+
+        if !(3..=13).contains(&n) {
+            return false;
+        }
+
+        let mut tiles = tiles
+            .iter()
+            .filter(|tile| !tile.is_free_wildcard())
+            .collect::<Vec<_>>();
+
+        if tiles.is_empty() {
+            return true;
+        }
+
+        tiles.sort_unstable();
+
+        let is_group = n <= 4
+            && tiles.iter().all(|tile| tile.number == tiles[0].number)
+            && tiles.windows(2).all(|pair| pair[0].color != pair[1].color);
+
+        let low = tiles[0].number as usize;
+        let high = tiles[tiles.len() - 1].number as usize;
+
+        let is_run = tiles.iter().all(|tile| tile.color == tiles[0].color)
+            && tiles
+                .windows(2)
+                .all(|pair| pair[0].number != pair[1].number)
+            && high - low < n;
+
+        is_group || is_run
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{tile_color::TileColor, Command};
+
+    fn run(numbers: &[u8], color: TileColor) -> Vec<Tile> {
+        numbers
+            .iter()
+            .map(|&number| Tile::new(number, color, false))
+            .collect()
+    }
+
+    #[test]
+    fn test_is_valid_set() {
+        assert!(Game::is_valid_set(&run(&[3, 4, 5], TileColor::Red)));
+        assert!(!Game::is_valid_set(&run(&[3, 4], TileColor::Red)));
+        assert!(!Game::is_valid_set(&run(&[3, 4, 6], TileColor::Red)));
+        assert!(!Game::is_valid_set(&run(&[3, 3, 4], TileColor::Red)));
+
+        let mut with_joker = run(&[3, 5], TileColor::Red);
+        with_joker.push(Tile::new(251, TileColor::Red, true));
+        assert!(Game::is_valid_set(&with_joker));
+
+        let group = vec![
+            Tile::new(10, TileColor::Red, false),
+            Tile::new(10, TileColor::Blue, false),
+            Tile::new(10, TileColor::Black, false),
+        ];
+        assert!(Game::is_valid_set(&group));
+
+        let mut duplicate_color = group.clone();
+        duplicate_color.push(Tile::new(10, TileColor::Red, false));
+        assert!(!Game::is_valid_set(&duplicate_color));
+    }
+
+    #[test]
+    fn test_pinned_joker() {
+        let mut pinned = run(&[5, 6], TileColor::Red);
+        pinned.push(Tile::new_pinned(7, TileColor::Red));
+        assert!(Game::is_valid_set(&pinned));
+
+        // r5 r6 r(w9) leaves a gap the joker was pinned not to fill
+        pinned[2] = Tile::new_pinned(9, TileColor::Red);
+        assert!(!Game::is_valid_set(&pinned));
+
+        pinned[2] = Tile::new_pinned(7, TileColor::Blue);
+        assert!(!Game::is_valid_set(&pinned));
+
+        let mut group = vec![
+            Tile::new(10, TileColor::Red, false),
+            Tile::new(10, TileColor::Blue, false),
+            Tile::new_pinned(10, TileColor::Black),
+        ];
+        assert!(Game::is_valid_set(&group));
+
+        group[2] = Tile::new_pinned(10, TileColor::Red);
+        assert!(!Game::is_valid_set(&group));
+
+        group[2] = Tile::new_pinned(11, TileColor::Black);
+        assert!(!Game::is_valid_set(&group));
+    }
+
+    #[test]
+    fn test_commit_and_rollback() {
+        let mut game = Game::new_with_board(vec![
+            run(&[6], TileColor::Red),
+            run(&[3, 4, 5], TileColor::Red),
+        ]);
+
+        assert!(game.commit_turn().is_err());
+
+        game.begin_turn().unwrap();
+        assert!(game.begin_turn().is_err());
+        game.stage(GameOperation::new(
+            Command::Add,
+            1,
+            run(&[6], TileColor::Red),
+            None,
+        ))
+        .unwrap();
+        game.board[0].clear();
+        assert!(game.commit_turn().is_ok());
+        assert_eq!(game.board[1].len(), 4);
+        assert!(!game.is_turn_started());
+
+        game.begin_turn().unwrap();
+        game.stage(GameOperation::new(
+            Command::Add,
+            1,
+            run(&[9], TileColor::Blue),
+            None,
+        ))
+        .unwrap();
+        assert!(matches!(
+            game.commit_turn(),
+            Err(TurnError::InvalidSet { index: 1 })
+        ));
+        assert_eq!(game.board[1], run(&[3, 4, 5, 6], TileColor::Red));
+
+        game.begin_turn().unwrap();
+        let tile = game.board[1].pop().unwrap();
+        game.board[0].push(tile);
+        assert!(matches!(game.commit_turn(), Err(TurnError::TileToHand(_))));
+        assert!(game.board[0].is_empty());
+
+        game.begin_turn().unwrap();
+        game.board[1].clear();
+        game.rollback_turn().unwrap();
+        assert_eq!(game.board[1].len(), 4);
+    }
+
+    #[test]
+    fn test_table_tiles_kept() {
+        let mut game = Game::new_with_board(vec![
+            run(&[6], TileColor::Red),
+            run(&[3, 4, 5], TileColor::Red),
+            run(&[7, 8, 9], TileColor::Blue),
+        ]);
+
+        // deleting a set loses its tiles
+        game.begin_turn().unwrap();
+        game.board[2].clear();
+        assert!(matches!(game.commit_turn(), Err(TurnError::TileLost(_))));
+        assert_eq!(game.board[2].len(), 3);
+
+        // a set from nowhere adds tiles
+        game.begin_turn().unwrap();
+        game.board.push(run(&[1, 2, 3], TileColor::Orange));
+        assert!(matches!(game.commit_turn(), Err(TurnError::TileAdded(_))));
+        assert_eq!(game.board.len(), 3);
+
+        // rearranging the table and playing from the hand keeps every tile
+        game.begin_turn().unwrap();
+        let hand = game.board[0].split_off(0);
+        game.board[1].extend(hand);
+        game.board[2].rotate_left(1);
+        assert!(game.commit_turn().is_ok());
+    }
+}
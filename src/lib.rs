@@ -0,0 +1,50 @@
+//! Rummikub board tracking and solving.
+//!
+//! A [`Game`] holds the board, `board[0]` being the hand. Commands such as `p(3,4,5)r` are
+//! parsed into a [`CommandAst`], validated against the board into a [`TileCommand`] and
+//! applied as a [`GameOperation`]. [`Solver`] searches for an arrangement of every tile into
+//! valid runs and groups.
+//!
+//! ```
+//! use rummy_app::{command_sequence_to_operations, Game, Solver};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut game: Game = "r3 r4 r5 w; b10 h10 o10".parse()?;
+//!
+//! for operation in command_sequence_to_operations("f(3,4,5)r", &game)? {
+//!     game.operate(operation)?;
+//! }
+//!
+//! let sets = Solver::new(game).solve().into_sets().expect("every tile fits a set");
+//! assert_eq!(sets.len(), 2);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every fallible step has its own error type: [`ParseError`] for syntax, [`TileCommandError`]
+//! for commands that do not fit the board, [`OperationError`] for operations, [`TurnError`]
+//! for turns, [`NotationError`], [`JsonError`] and [`JournalError`] for the text, JSON and
+//! journal formats.
+//!
+//! The [`ffi`] module exposes the solver to C and other native callers through the cdylib
+//! and `include/rummy_app.h`.
+
+pub mod ffi;
+pub mod game;
+pub mod solver;
+
+pub use game::input::{apply_input, execute_input, Applied};
+pub use game::journal::{Journal, JournalError};
+pub use game::json::JsonError;
+pub use game::notation::{NotationError, TileSet};
+pub use game::parser::{
+    command_sequence_to_operations, command_to_tile_command, commands_to_tile_commands, parse_hand,
+    parse_init, parse_player, CommandAst, ParseError, Parser,
+};
+pub use game::player::{Player, PlayerCommand, PlayerError};
+pub use game::tile::Tile;
+pub use game::tile_color::TileColor;
+pub use game::tile_command::{TileCommand, TileCommandError};
+pub use game::turn::TurnError;
+pub use game::{Command, Game, GameOperation, OperationError};
+pub use solver::{Solution, SolveOutcome, Solver};
@@ -1,10 +1,61 @@
-use std::io::Result as ioResult;
+use cli::{Mode, INVALID_INPUT, USAGE};
+use protocol::Session;
+use repl::Repl;
+use std::env;
+use std::io::{self, IsTerminal};
+use std::process::ExitCode;
 use views::TUI;
 
-pub mod game;
-pub mod solver;
-pub mod views;
+mod cli;
+mod protocol;
+mod repl;
+mod server;
+mod views;
 
-fn main() -> ioResult<()> {
-    TUI::new().run()
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    match cli::parse_args(&args) {
+        Ok(Mode::Tui) => match TUI::new().run() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Ok(Mode::Help) => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Ok(Mode::Solve(options)) => cli::solve(&options),
+        Ok(Mode::Repl) => {
+            let prompt = io::stdin().is_terminal();
+
+            match Repl::new().run(io::stdin().lock(), io::stdout(), prompt) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Ok(Mode::Json) => match Session::new().run(io::stdin().lock(), io::stdout()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Ok(Mode::Serve(options)) => match server::serve(&options) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(INVALID_INPUT)
+        }
+    }
 }
@@ -0,0 +1,663 @@
+use rummy_app::{
+    apply_input, Game, JsonError, NotationError, ParseError, Solution, SolveOutcome, Solver, Tile,
+    TileCommandError,
+};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+/// Bumped whenever a request or response changes shape.
+pub const PROTOCOL_VERSION: u64 = 1;
+
+pub const METHODS: [&str; 6] = ["hello", "set_board", "apply", "solve", "hint", "analyze"];
+
+/// How long the searches of one `solve`, `hint` or `analyze` request may take together.
+pub const SEARCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// `hint` tries at most this many tiles, pairs and sets of the hand per tile it lays down
+const HINT_CANDIDATES: usize = 200;
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    InvalidJson(String),
+    InvalidRequest(String),
+    UnknownMethod(String),
+    HandshakeRequired,
+    UnsupportedVersion(Option<u64>),
+    Notation(NotationError),
+    Json(JsonError),
+    Command(TileCommandError),
+    UnsolvableTable,
+    Timeout(Duration),
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::InvalidJson(msg) => write!(f, "Invalid JSON: {}", msg),
+            ProtocolError::InvalidRequest(msg) => write!(f, "Invalid request: {}", msg),
+            ProtocolError::UnknownMethod(method) => write!(f, "Unknown method '{}'!", method),
+            ProtocolError::HandshakeRequired => {
+                write!(f, "Send 'hello' with the protocol version first!")
+            }
+            ProtocolError::UnsupportedVersion(Some(version)) => write!(
+                f,
+                "Protocol version {} is not supported, only {}!",
+                version, PROTOCOL_VERSION
+            ),
+            ProtocolError::UnsupportedVersion(None) => {
+                write!(f, "The protocol version is missing!")
+            }
+            ProtocolError::Notation(e) => write!(f, "Invalid board: {}", e),
+            ProtocolError::Json(e) => write!(f, "Invalid board: {}", e),
+            ProtocolError::Command(e) => write!(f, "{}", e),
+            ProtocolError::UnsolvableTable => {
+                write!(
+                    f,
+                    "The sets on the table cannot be arranged into valid sets!"
+                )
+            }
+            ProtocolError::Timeout(timeout) => write!(
+                f,
+                "The search took longer than {} ms and was cancelled!",
+                timeout.as_millis()
+            ),
+        }
+    }
+}
+
+impl Error for ProtocolError {}
+
+impl From<TileCommandError> for ProtocolError {
+    fn from(e: TileCommandError) -> Self {
+        ProtocolError::Command(e)
+    }
+}
+
+impl ProtocolError {
+    /// The `error` object of a response, a stable `code` and the message plus any details.
+    pub fn to_json(&self) -> Value {
+        let code = match self {
+            ProtocolError::InvalidJson(_) => "invalid_json",
+            ProtocolError::InvalidRequest(_) => "invalid_request",
+            ProtocolError::UnknownMethod(_) => "unknown_method",
+            ProtocolError::HandshakeRequired => "handshake_required",
+            ProtocolError::UnsupportedVersion(_) => "unsupported_version",
+            ProtocolError::Notation(_) | ProtocolError::Json(_) => "invalid_board",
+            ProtocolError::Command(e) => return command_error_json(e),
+            ProtocolError::UnsolvableTable => "unsolvable_table",
+            ProtocolError::Timeout(_) => "timeout",
+        };
+
+        let mut body = json!({ "code": code, "message": self.to_string() });
+
+        match self {
+            ProtocolError::UnsupportedVersion(_) => {
+                body["supported"] = json!([PROTOCOL_VERSION]);
+            }
+            ProtocolError::Notation(NotationError::Syntax(errors)) => {
+                body["errors"] = parse_errors_json(errors);
+            }
+            ProtocolError::Notation(NotationError::Line { line, errors }) => {
+                body["line"] = json!(line);
+                body["errors"] = parse_errors_json(errors);
+            }
+            ProtocolError::Notation(NotationError::Tiles(e)) => {
+                body["cause"] = command_error_json(e);
+            }
+            _ => {}
+        }
+
+        body
+    }
+}
+
+fn command_error_json(error: &TileCommandError) -> Value {
+    let code = match error {
+        TileCommandError::InvalidCommand => "invalid_command",
+        TileCommandError::InvalidIndex => "invalid_index",
+        TileCommandError::InvalidArgs => "invalid_args",
+        TileCommandError::NotInRow(_) => "not_in_row",
+        TileCommandError::InvalidSet(_) => "invalid_set",
+        TileCommandError::Syntax(_) => "syntax",
+        TileCommandError::Sequence { .. } => "sequence",
+        TileCommandError::Operation(_) => "operation",
+        TileCommandError::Player(_) => "player",
+        TileCommandError::Turn(_) => "turn",
+        TileCommandError::Other(_) => "other",
+    };
+
+    let mut body = json!({ "code": code, "message": error.to_string() });
+
+    match error {
+        TileCommandError::NotInRow(index) | TileCommandError::InvalidSet(index) => {
+            body["index"] = json!(index);
+        }
+        TileCommandError::Syntax(errors) => body["errors"] = parse_errors_json(errors),
+        TileCommandError::Sequence {
+            position,
+            command,
+            error,
+        } => {
+            body["position"] = json!(position);
+            body["command"] = json!(command);
+            body["cause"] = command_error_json(error);
+        }
+        _ => {}
+    }
+
+    body
+}
+
+fn parse_errors_json(errors: &[ParseError]) -> Value {
+    errors
+        .iter()
+        .map(|e| {
+            json!({
+                "column": e.column,
+                "expected": e.expected,
+                "found": e.found,
+                "message": e.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// One subprocess session: a JSON request per input line, a JSON response per output line.
+pub struct Session {
+    game: Game,
+    handshake: bool,
+    timeout: Duration,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session {
+            game: Game::new(),
+            handshake: false,
+            timeout: SEARCH_TIMEOUT,
+        }
+    }
+
+    /// Answer every non-empty line until the end of the input.
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            writeln!(output, "{}", self.handle_line(&line))?;
+            output.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// The response to one request, `id` is echoed back as given or `null` if it is missing.
+    pub fn handle_line(&mut self, line: &str) -> Value {
+        let request = match serde_json::from_str::<Value>(line) {
+            Ok(request) => request,
+            Err(e) => {
+                return error_response(Value::Null, ProtocolError::InvalidJson(e.to_string()))
+            }
+        };
+
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+
+        match self.handle(&request) {
+            Ok(result) => json!({ "id": id, "result": result }),
+            Err(e) => error_response(id, e),
+        }
+    }
+
+    fn handle(&mut self, request: &Value) -> Result<Value, ProtocolError> {
+        let method = request
+            .get("method")
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                ProtocolError::InvalidRequest("'method' must be a string".to_string())
+            })?;
+        let params = request.get("params").cloned().unwrap_or(json!({}));
+
+        if method == "hello" {
+            return self.hello(&params);
+        }
+
+        if !METHODS.contains(&method) {
+            return Err(ProtocolError::UnknownMethod(method.to_string()));
+        }
+
+        if !self.handshake {
+            return Err(ProtocolError::HandshakeRequired);
+        }
+
+        match method {
+            "set_board" => {
+                self.game = read_board(&params)?;
+                self.board()
+            }
+            "apply" => {
+                let command = string_param(&params, "command")?;
+                apply_input(&mut self.game, command)?;
+                self.board()
+            }
+            "solve" => {
+                let outcome = Solver::new(self.game.clone())
+                    .with_deadline(Instant::now() + self.timeout)
+                    .solve();
+
+                match outcome {
+                    SolveOutcome::Cancelled => Err(ProtocolError::Timeout(self.timeout)),
+                    outcome => to_value(&Solution::new(outcome.into_sets())),
+                }
+            }
+            "hint" => hint(&self.game, self.timeout),
+            _ => Ok(analyze(&self.game, self.timeout)),
+        }
+    }
+
+    fn hello(&mut self, params: &Value) -> Result<Value, ProtocolError> {
+        let version = params.get("protocol").and_then(Value::as_u64);
+
+        if version != Some(PROTOCOL_VERSION) {
+            return Err(ProtocolError::UnsupportedVersion(version));
+        }
+
+        self.handshake = true;
+
+        Ok(json!({
+            "protocol": PROTOCOL_VERSION,
+            "version": env!("CARGO_PKG_VERSION"),
+            "methods": METHODS,
+        }))
+    }
+
+    fn board(&self) -> Result<Value, ProtocolError> {
+        Ok(json!({ "board": to_value(&self.game)? }))
+    }
+}
+
+fn error_response(id: Value, error: ProtocolError) -> Value {
+    json!({ "id": id, "error": error.to_json() })
+}
+
+fn to_value(value: &impl serde::Serialize) -> Result<Value, ProtocolError> {
+    serde_json::to_value(value).map_err(|e| ProtocolError::Json(e.into()))
+}
+
+fn string_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, ProtocolError> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| ProtocolError::InvalidRequest(format!("'params.{}' must be a string", name)))
+}
+
+/// `params.board` is either the text notation or a `Game` in the JSON schema.
+pub fn read_board(params: &Value) -> Result<Game, ProtocolError> {
+    match params.get("board") {
+        Some(Value::String(text)) => text.parse::<Game>().map_err(ProtocolError::Notation),
+        Some(board @ Value::Object(_)) => {
+            Game::from_json(&board.to_string()).map_err(ProtocolError::Json)
+        }
+        _ => Err(ProtocolError::InvalidRequest(
+            "'params.board' must be a string or an object".to_string(),
+        )),
+    }
+}
+
+// the table without the hand and without the rows left empty by deleted sets
+fn table(game: &Game) -> Vec<Vec<Tile>> {
+    game.board[1..]
+        .iter()
+        .filter(|set| !set.is_empty())
+        .cloned()
+        .collect()
+}
+
+/// Hand tiles that can be played together, found by adding single tiles, pairs and new sets
+/// from the hand as long as the table still solves, and the table they result in. Every
+/// search shares one deadline, `timeout` after the request came in.
+fn hint(game: &Game, timeout: Duration) -> Result<Value, ProtocolError> {
+    let deadline = Instant::now() + timeout;
+    let table = table(game);
+    let solve = |tiles: &[Tile]| {
+        let mut board = vec![tiles.to_vec()];
+        board.extend(table.iter().cloned());
+
+        match Solver::new(Game::new_with_board(board))
+            .with_deadline(deadline)
+            .solve()
+        {
+            SolveOutcome::Cancelled => Err(ProtocolError::Timeout(timeout)),
+            outcome => Ok(outcome.into_sets()),
+        }
+    };
+
+    let mut rest = game.board[0].clone();
+
+    if let Some(sets) = solve(&rest)? {
+        return Ok(json!({ "tiles": rest, "sets": sets }));
+    }
+
+    let mut sets = solve(&[])?.ok_or(ProtocolError::UnsolvableTable)?;
+    let mut played = Vec::new();
+
+    'search: loop {
+        for candidate in candidates(&rest, HINT_CANDIDATES) {
+            let mut tiles = played.clone();
+            tiles.extend(candidate.iter().map(|&i| rest[i].clone()));
+
+            if let Some(solution) = solve(&tiles)? {
+                played = tiles;
+                sets = solution;
+
+                for &i in candidate.iter().rev() {
+                    rest.remove(i);
+                }
+
+                continue 'search;
+            }
+        }
+
+        break;
+    }
+
+    Ok(json!({ "tiles": played, "sets": sets }))
+}
+
+// the hand indices worth trying: every tile, pairs that could share a set, and new sets,
+// at most `limit` of them so a large hand does not build every triple
+fn candidates(hand: &[Tile], limit: usize) -> Vec<Vec<usize>> {
+    let n = hand.len();
+    let could_share = |a: &Tile, b: &Tile| {
+        a.is_wildcard
+            || b.is_wildcard
+            || (a.number == b.number && a.color != b.color)
+            || (a.color == b.color && a.number != b.number && a.number.abs_diff(b.number) <= 2)
+    };
+
+    let mut candidates = (0..n.min(limit)).map(|i| vec![i]).collect::<Vec<_>>();
+
+    for i in 0..n {
+        for j in i + 1..n {
+            if candidates.len() == limit {
+                return candidates;
+            }
+
+            if could_share(&hand[i], &hand[j]) {
+                candidates.push(vec![i, j]);
+            }
+        }
+    }
+
+    for i in 0..n {
+        for j in i + 1..n {
+            for k in j + 1..n {
+                if candidates.len() == limit {
+                    return candidates;
+                }
+
+                let set = [hand[i].clone(), hand[j].clone(), hand[k].clone()];
+
+                if Game::is_valid_set(&set) {
+                    candidates.push(vec![i, j, k]);
+                }
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Counts of the hand and the table, the indices of invalid sets and whether every tile fits,
+/// `null` if that search took longer than `timeout`.
+fn analyze(game: &Game, timeout: Duration) -> Value {
+    let hand = &game.board[0];
+    let invalid_sets = game
+        .board
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, set)| !set.is_empty() && !Game::is_valid_set(set))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    let table = table(game);
+
+    json!({
+        "hand_tiles": hand.len(),
+        "jokers": hand.iter().filter(|tile| tile.is_wildcard).count(),
+        "table_sets": table.len(),
+        "table_tiles": table.iter().map(Vec::len).sum::<usize>(),
+        "invalid_sets": invalid_sets,
+        "solvable": match Solver::new(game.clone()).with_deadline(Instant::now() + timeout).solve() {
+            SolveOutcome::Cancelled => Value::Null,
+            outcome => json!(outcome.is_solved()),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rummy_app::TileSet;
+
+    fn session() -> Session {
+        let mut session = Session::new();
+        let response =
+            session.handle_line(r#"{"id": 0, "method": "hello", "params": {"protocol": 1}}"#);
+        assert_eq!(response["result"]["protocol"], 1);
+
+        session
+    }
+
+    fn request(session: &mut Session, id: u64, method: &str, params: Value) -> Value {
+        let line = json!({ "id": id, "method": method, "params": params }).to_string();
+        let response = session.handle_line(&line);
+        assert_eq!(response["id"], id);
+
+        response
+    }
+
+    fn tiles(value: &Value) -> Vec<String> {
+        serde_json::from_value::<Vec<Tile>>(value.clone())
+            .unwrap()
+            .iter()
+            .map(Tile::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_handshake() {
+        let mut session = Session::new();
+
+        let response = request(&mut session, 1, "solve", json!({}));
+        assert_eq!(response["error"]["code"], "handshake_required");
+
+        let response = request(&mut session, 2, "hello", json!({ "protocol": 2 }));
+        assert_eq!(response["error"]["code"], "unsupported_version");
+        assert_eq!(response["error"]["supported"], json!([1]));
+
+        let response = request(&mut session, 3, "hello", json!({ "protocol": 1 }));
+        assert_eq!(response["result"]["version"], env!("CARGO_PKG_VERSION"));
+
+        let response = request(&mut session, 4, "solve", json!({}));
+        assert_eq!(response["result"], json!({ "solved": true, "sets": [] }));
+
+        let response = request(&mut session, 5, "deal", json!({}));
+        assert_eq!(response["error"]["code"], "unknown_method");
+
+        let response = session.handle_line("{\"id\": 6, ");
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["error"]["code"], "invalid_json");
+    }
+
+    #[test]
+    fn test_board_and_commands() {
+        let mut session = session();
+
+        let response = request(
+            &mut session,
+            1,
+            "set_board",
+            json!({ "board": "r3 r4 r5 w; b10 h10 o10" }),
+        );
+        assert_eq!(
+            response["result"]["board"]["board"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let board = response["result"]["board"].clone();
+        let response = request(&mut session, 2, "set_board", json!({ "board": board }));
+        assert!(response.get("result").is_some(), "{}", response);
+
+        let response = request(&mut session, 3, "apply", json!({ "command": "f(3,4,5)r" }));
+        assert_eq!(
+            tiles(&response["result"]["board"]["board"][2]),
+            vec!["r3", "r4", "r5"]
+        );
+
+        let response = request(&mut session, 4, "solve", json!({}));
+        assert_eq!(response["result"]["solved"], true);
+        assert_eq!(response["result"]["sets"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut session = session();
+
+        let response = request(
+            &mut session,
+            1,
+            "set_board",
+            json!({ "board": "r3\nb1 x2" }),
+        );
+        let error = &response["error"];
+        assert_eq!(error["code"], "invalid_board");
+        assert_eq!(error["line"], 2);
+        assert_eq!(error["errors"][0]["column"], 3);
+
+        let response = request(&mut session, 2, "apply", json!({ "command": "f(3,4,5)r" }));
+        assert_eq!(response["error"]["code"], "not_in_row");
+        assert_eq!(response["error"]["index"], 0);
+
+        let response = request(&mut session, 3, "apply", json!({ "command": "a0(3)r; x2" }));
+        let error = &response["error"];
+        assert_eq!(error["code"], "sequence");
+        assert_eq!(error["position"], 2);
+        assert_eq!(error["cause"]["code"], "invalid_index");
+
+        let response = request(&mut session, 4, "apply", json!({ "command": "a0(3,x)r" }));
+        assert_eq!(response["error"]["code"], "syntax");
+        assert_eq!(response["error"]["errors"][0]["column"], 5);
+
+        let response = request(&mut session, 5, "apply", json!({}));
+        assert_eq!(response["error"]["code"], "invalid_request");
+    }
+
+    #[test]
+    fn test_hint_and_analyze() {
+        let mut session = session();
+
+        request(
+            &mut session,
+            1,
+            "set_board",
+            json!({ "board": "r3 r4 r5 b1 b9; r6 r7 r8; h10 b10 o10" }),
+        );
+
+        let response = request(&mut session, 2, "hint", json!({}));
+        let mut played = tiles(&response["result"]["tiles"]);
+        played.sort();
+        assert_eq!(played, vec!["r3", "r4", "r5"]);
+
+        let response = request(&mut session, 3, "analyze", json!({}));
+        assert_eq!(
+            response["result"],
+            json!({
+                "hand_tiles": 5,
+                "jokers": 0,
+                "table_sets": 2,
+                "table_tiles": 6,
+                "invalid_sets": [],
+                "solvable": false,
+            })
+        );
+
+        request(
+            &mut session,
+            4,
+            "set_board",
+            json!({ "board": "r3; r6 r7" }),
+        );
+
+        let response = request(&mut session, 5, "hint", json!({}));
+        assert_eq!(response["error"]["code"], "unsolvable_table");
+
+        let response = request(&mut session, 6, "analyze", json!({}));
+        assert_eq!(response["result"]["invalid_sets"], json!([1]));
+
+        // the candidates stop at the limit instead of being cut after building all of them
+        let hand = "r:1-13 b:1-13".parse::<TileSet>().unwrap().0;
+        assert_eq!(candidates(&hand, 107).len(), 107);
+        assert_eq!(candidates(&hand, 50).len(), 50);
+        assert_eq!(candidates(&hand[..3], 300).len(), 3 + 3 + 1);
+    }
+
+    #[test]
+    fn test_timeout() {
+        assert_eq!(Session::default().timeout, SEARCH_TIMEOUT);
+
+        let mut session = session();
+        session.timeout = Duration::ZERO;
+
+        request(
+            &mut session,
+            1,
+            "set_board",
+            json!({ "board": "r3 r4 r5 b1; r6 r7 r8" }),
+        );
+
+        // the deadline has passed before the first search node
+        for (id, method) in [(2, "solve"), (3, "hint")] {
+            let response = request(&mut session, id, method, json!({}));
+            assert_eq!(response["error"]["code"], "timeout", "{}", method);
+        }
+
+        let response = request(&mut session, 4, "analyze", json!({}));
+        assert_eq!(response["result"]["hand_tiles"], 4);
+        assert_eq!(response["result"]["solvable"], Value::Null);
+    }
+
+    #[test]
+    fn test_run() {
+        let input = "{\"id\": \"a\", \"method\": \"hello\", \"params\": {\"protocol\": 1}}\n\n{\"id\": \"b\", \"method\": \"solve\"}\n";
+        let mut output = Vec::new();
+
+        Session::new().run(input.as_bytes(), &mut output).unwrap();
+
+        let lines = String::from_utf8(output).unwrap();
+        let responses = lines
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(
+            responses[1],
+            json!({ "id": "b", "result": { "solved": true, "sets": [] } })
+        );
+    }
+}
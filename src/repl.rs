@@ -0,0 +1,295 @@
+use rummy_app::game::journal::Journal;
+use rummy_app::{execute_input, Game, ParseError, Solver, TileCommandError, TileSet};
+use std::io::{self, BufRead, Write};
+
+pub const HELP: &str = "Commands:
+    hand TILES      Add tiles to your hand, e.g. hand r3 r4 b10 w
+    a0(3)r, f(...)  Any board command of the TUI, several separated by ';'
+    join, next, ... The player commands of the TUI
+    begin, commit   Stage the following commands as one turn, checked on commit
+    rollback        Undo the commands of the open turn
+    show            Print the board
+    solve           Print a solution that uses every tile
+    undo            Take back the last command
+    save PATH       Save the journal as JSON lines
+    help            Print this help
+    q, quit         Leave, as does the end of the input";
+
+/// A line-oriented session on plain stdin and stdout, for terminals without cursor control.
+pub struct Repl {
+    game: Game,
+    journal: Journal,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Repl {
+            game: Game::new(),
+            journal: Journal::new(),
+        }
+    }
+
+    /// Read commands until `q` or the end of the input, printing the result of each.
+    pub fn run(
+        &mut self,
+        input: impl BufRead,
+        mut output: impl Write,
+        prompt: bool,
+    ) -> io::Result<()> {
+        writeln!(output, "Rummy Solver - enter 'help' for the commands.")?;
+
+        let mut lines = input.lines();
+
+        loop {
+            if prompt {
+                write!(output, "> ")?;
+                output.flush()?;
+            }
+
+            let Some(line) = lines.next() else {
+                break;
+            };
+
+            match self.execute(line?.trim()) {
+                Some(text) => write!(output, "{}", text)?,
+                None => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The text to print for one input line, `None` when the session should end.
+    pub fn execute(&mut self, input: &str) -> Option<String> {
+        let result = match input {
+            "" => return Some(String::new()),
+            "q" | "quit" | "exit" => return None,
+            "help" => return Some(format!("{}\n", HELP)),
+            "show" => Ok(()),
+            "solve" => return Some(self.solve()),
+            "undo" => self.undo(),
+            _ => {
+                if let Some(path) = input.strip_prefix("save ") {
+                    return Some(match self.journal.save(path.trim()) {
+                        Ok(()) => format!(
+                            "Saved {} commands to {}.\n",
+                            self.journal.len(),
+                            path.trim()
+                        ),
+                        Err(e) => format!("error: {}\n", e),
+                    });
+                }
+
+                execute_input(&mut self.game, &mut self.journal, input)
+            }
+        };
+
+        Some(match result {
+            Ok(()) => format_game(&self.game),
+            Err(e) => format_error(input, e),
+        })
+    }
+
+    // replaying the journal without its last entry rebuilds the game before it, an open
+    // turn is opened again so its remaining commands stay staged
+    fn undo(&mut self) -> Result<(), TileCommandError> {
+        let turn_started = self.game.is_turn_started();
+
+        if turn_started && self.journal.len() == self.journal.turn_start() {
+            return Err(TileCommandError::Other(
+                "Nothing to undo in this turn, rollback ends it.".to_string(),
+            ));
+        }
+
+        if self.journal.is_empty() {
+            return Err(TileCommandError::Other("Nothing to undo.".to_string()));
+        }
+
+        let len = self.journal.len() - 1;
+        let game = if turn_started {
+            self.journal.replay_turn_to(len)
+        } else {
+            self.journal.replay_to(len)
+        };
+
+        self.game = game.map_err(|e| TileCommandError::Other(e.to_string()))?;
+        self.journal.truncate(len);
+
+        Ok(())
+    }
+
+    fn solve(&self) -> String {
+        match Solver::new(self.game.clone()).solve().into_sets() {
+            Some(sets) => {
+                let mut text = String::from("Solved:\n");
+
+                for (i, set) in sets.into_iter().enumerate() {
+                    text.push_str(&format!("{:>4}: {}\n", i + 1, TileSet(set)));
+                }
+
+                text
+            }
+            None => "Not solved, not every tile fits into a valid set.\n".to_string(),
+        }
+    }
+}
+
+/// The hand, every set that is still on the table with its index, and the players.
+pub fn format_game(game: &Game) -> String {
+    let mut text = format!("hand: {}\n", TileSet(game.board[0].clone()));
+
+    for (i, row) in game.board.iter().enumerate().skip(1) {
+        // deleted sets leave an empty row behind so the other indices stay the same
+        if !row.is_empty() {
+            text.push_str(&format!("{:>4}: {}\n", i, TileSet(row.clone())));
+        }
+    }
+
+    if game.players.len() > 1 {
+        text.push_str("players:\n");
+
+        for (i, player) in game.players.iter().enumerate() {
+            let turn = if i == game.current_turn { ">" } else { " " };
+            let meld = if player.has_initial_meld {
+                "melded"
+            } else {
+                "no initial meld"
+            };
+
+            text.push_str(&format!(
+                "  {} {}: {} - {} tiles, {}",
+                turn,
+                i,
+                player.name,
+                game.tile_count(i).unwrap_or_default(),
+                meld
+            ));
+
+            if !player.known_tiles.is_empty() {
+                text.push_str(&format!(", known: {}", TileSet(player.known_tiles.clone())));
+            }
+
+            text.push('\n');
+        }
+    }
+
+    text
+}
+
+// syntax errors repeat the input with a caret under every offending column
+fn format_error(input: &str, error: TileCommandError) -> String {
+    match error {
+        TileCommandError::Syntax(errors) => {
+            let mut carets = vec![' '; input.chars().count() + 1];
+
+            for error in &errors {
+                if let Some(c) = carets.get_mut(error.column) {
+                    *c = '^';
+                }
+            }
+
+            let carets = carets.into_iter().collect::<String>();
+            let messages = errors
+                .iter()
+                .map(ParseError::to_string)
+                .map(|e| format!("error: {}\n", e))
+                .collect::<String>();
+
+            format!("  {}\n  {}\n{}", input, carets.trim_end(), messages)
+        }
+        e => format!("error: {}\n", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(lines: &[&str]) -> (Repl, Vec<String>) {
+        let mut repl = Repl::new();
+        let outputs = lines
+            .iter()
+            .map(|line| repl.execute(line).expect(line))
+            .collect();
+
+        (repl, outputs)
+    }
+
+    #[test]
+    fn test_commands() {
+        let (repl, outputs) = session(&["hand r3 r4 r5 w b10 h10 o10", "f(3,4,5)r", "show"]);
+
+        assert_eq!(outputs[0], "hand: h10 b10 o10 r3 r4 r5 w\n");
+        assert_eq!(outputs[1], "hand: h10 b10 o10 w\n   1: r3 r4 r5\n");
+        assert_eq!(outputs[2], outputs[1]);
+        assert_eq!(repl.journal.len(), 2);
+    }
+
+    #[test]
+    fn test_undo() {
+        let (repl, outputs) = session(&["hand r3 r4 r5", "f(3,4,5)r", "undo", "undo", "undo"]);
+
+        assert_eq!(outputs[2], "hand: r3 r4 r5\n");
+        assert_eq!(outputs[3], "hand: \n");
+        assert_eq!(outputs[4], "error: Nothing to undo.\n");
+        assert!(repl.journal.is_empty());
+    }
+
+    #[test]
+    fn test_undo_in_turn() {
+        let (repl, outputs) = session(&[
+            "hand r3 r4 r5 r6",
+            "begin",
+            "f(3,4,5)r",
+            "undo",
+            "undo",
+            "f(3,4,5,6)r",
+            "commit",
+        ]);
+
+        assert_eq!(outputs[3], "hand: r3 r4 r5 r6\n");
+        assert!(outputs[4].starts_with("error: Nothing to undo in this turn"));
+        assert_eq!(outputs[6], "hand: \n   1: r3 r4 r5 r6\n");
+        assert!(!repl.game.is_turn_started());
+        assert_eq!(repl.journal.len(), 2);
+    }
+
+    #[test]
+    fn test_errors() {
+        let (repl, outputs) = session(&["hand r3 x r5", "a1(3)r", "x0"]);
+
+        assert_eq!(
+            outputs[0],
+            "  hand r3 x r5\n          ^\nerror: Column 9: expected a tile such as r3, r(3), r:3,4,5 or w, found 'x'!\n"
+        );
+        assert!(outputs[1].starts_with("error: "), "{}", outputs[1]);
+        assert!(outputs[2].starts_with("error: "), "{}", outputs[2]);
+
+        // nothing was applied or recorded
+        assert_eq!(repl.game.board, vec![vec![]]);
+        assert!(repl.journal.is_empty());
+    }
+
+    #[test]
+    fn test_run() {
+        let input = "hand r3 r4 r5\nsolve\nq\nhand r6\n";
+        let mut output = Vec::new();
+
+        Repl::new()
+            .run(input.as_bytes(), &mut output, false)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(
+            output.ends_with("hand: r3 r4 r5\nSolved:\n   1: r3 r4 r5\n"),
+            "{}",
+            output
+        );
+    }
+}
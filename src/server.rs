@@ -0,0 +1,513 @@
+use crate::protocol::{read_board, ProtocolError};
+use rummy_app::{apply_input, Applied, Game, Solution, SolveOutcome, Solver};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Largest accepted request body, boards and commands are far smaller.
+pub const MAX_BODY: usize = 64 * 1024;
+const MAX_HEAD: usize = 8 * 1024;
+const MAX_CONNECTIONS: usize = 16;
+// a client that stops sending is dropped after this long
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServeOptions {
+    pub port: u16,
+    /// How long one `/solve` may search before it is cancelled.
+    pub timeout: Duration,
+}
+
+impl Default for ServeOptions {
+    fn default() -> Self {
+        ServeOptions {
+            port: 7878,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum HttpError {
+    Io(io::Error),
+    BadRequest(String),
+    LengthRequired,
+    TooLarge,
+    NotFound,
+    MethodNotAllowed,
+    Timeout(Duration),
+    Busy,
+    Panic,
+    Protocol(ProtocolError),
+}
+
+impl Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::Io(e) => write!(f, "{}", e),
+            HttpError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
+            HttpError::LengthRequired => write!(f, "A Content-Length header is required!"),
+            HttpError::TooLarge => write!(f, "The request is larger than {} bytes!", MAX_BODY),
+            HttpError::NotFound => write!(f, "No such endpoint, use /solve, /validate or /parse!"),
+            HttpError::MethodNotAllowed => write!(f, "Only POST is supported!"),
+            HttpError::Timeout(timeout) => write!(
+                f,
+                "No solution found within {} ms, the search was cancelled!",
+                timeout.as_millis()
+            ),
+            HttpError::Busy => write!(f, "Too many requests at once, try again later!"),
+            HttpError::Panic => write!(f, "The request failed with an internal error!"),
+            HttpError::Protocol(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for HttpError {}
+
+impl From<io::Error> for HttpError {
+    fn from(e: io::Error) -> Self {
+        HttpError::Io(e)
+    }
+}
+
+impl From<ProtocolError> for HttpError {
+    fn from(e: ProtocolError) -> Self {
+        HttpError::Protocol(e)
+    }
+}
+
+impl HttpError {
+    pub fn status(&self) -> u16 {
+        match self {
+            HttpError::Io(_) | HttpError::Panic => 500,
+            HttpError::BadRequest(_) => 400,
+            HttpError::LengthRequired => 411,
+            HttpError::TooLarge => 413,
+            HttpError::NotFound => 404,
+            HttpError::MethodNotAllowed => 405,
+            HttpError::Timeout(_) | HttpError::Busy => 503,
+            HttpError::Protocol(
+                ProtocolError::InvalidJson(_) | ProtocolError::InvalidRequest(_),
+            ) => 400,
+            HttpError::Protocol(_) => 422,
+        }
+    }
+
+    /// The same `error` object as the JSON-lines protocol.
+    pub fn to_json(&self) -> Value {
+        let code = match self {
+            HttpError::Io(_) => "io",
+            HttpError::BadRequest(_) => "bad_request",
+            HttpError::LengthRequired => "length_required",
+            HttpError::TooLarge => "too_large",
+            HttpError::NotFound => "not_found",
+            HttpError::MethodNotAllowed => "method_not_allowed",
+            HttpError::Timeout(_) => "timeout",
+            HttpError::Busy => "busy",
+            HttpError::Panic => "internal",
+            HttpError::Protocol(e) => return json!({ "error": e.to_json() }),
+        };
+
+        json!({ "error": { "code": code, "message": self.to_string() } })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+/// Serve until the process is stopped, on localhost only.
+pub fn serve(options: &ServeOptions) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", options.port))?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+
+    run(listener, options.timeout);
+
+    Ok(())
+}
+
+// one of the `MAX_CONNECTIONS` handler threads, given back when dropped, even by a panic
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    fn acquire(active: &Arc<AtomicUsize>) -> Option<Slot> {
+        let slot = Slot(active.clone());
+
+        // dropping the slot gives back what was just taken
+        (active.fetch_add(1, Ordering::SeqCst) < MAX_CONNECTIONS).then_some(slot)
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn run(listener: TcpListener, timeout: Duration) {
+    let active = Arc::new(AtomicUsize::new(0));
+
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+
+        let Some(slot) = Slot::acquire(&active) else {
+            let _ = write_response(&mut stream, Err(HttpError::Busy));
+            continue;
+        };
+
+        thread::spawn(move || {
+            let _slot = slot;
+            handle_connection(stream, timeout);
+        });
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, timeout: Duration) {
+    let request = stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .and_then(|()| stream.try_clone())
+        .map_err(HttpError::from)
+        .and_then(|reader| read_request(&mut BufReader::new(reader)));
+
+    let result = match request {
+        Ok(request) => catch_panic(|| route(&request, timeout)),
+        // the client is gone or stopped sending, there is no one to answer
+        Err(HttpError::Io(_)) => return,
+        Err(e) => Err(e),
+    };
+
+    let _ = write_response(&mut stream, result);
+}
+
+// a panicking handler answers 500 instead of dropping the connection without a response
+fn catch_panic(handler: impl FnOnce() -> Result<Value, HttpError>) -> Result<Value, HttpError> {
+    panic::catch_unwind(AssertUnwindSafe(handler)).unwrap_or(Err(HttpError::Panic))
+}
+
+/// Read the request line, the headers and a body of at most `MAX_BODY` bytes.
+pub fn read_request(reader: &mut impl BufRead) -> Result<Request, HttpError> {
+    let mut head = Vec::new();
+    let mut limited = reader.by_ref().take(MAX_HEAD as u64);
+
+    while !head.ends_with(b"\r\n\r\n") && !head.ends_with(b"\n\n") {
+        if limited.read_until(b'\n', &mut head)? == 0 {
+            return Err(if limited.limit() == 0 {
+                HttpError::TooLarge
+            } else {
+                HttpError::BadRequest("the request ended early".to_string())
+            });
+        }
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Err(HttpError::BadRequest("invalid request line".to_string()));
+    };
+
+    let mut content_length = None;
+
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                let length = value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| HttpError::BadRequest("invalid Content-Length".to_string()))?;
+                content_length = Some(length);
+            }
+        }
+    }
+
+    let length = match (method, content_length) {
+        (_, Some(length)) if length > MAX_BODY => return Err(HttpError::TooLarge),
+        (_, Some(length)) => length,
+        ("POST", None) => return Err(HttpError::LengthRequired),
+        (_, None) => 0,
+    };
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request {
+        method: method.to_string(),
+        path: target.split('?').next().unwrap_or_default().to_string(),
+        body,
+    })
+}
+
+pub fn route(request: &Request, timeout: Duration) -> Result<Value, HttpError> {
+    let handler = match request.path.as_str() {
+        "/solve" => solve,
+        "/validate" => validate,
+        "/parse" => parse,
+        _ => return Err(HttpError::NotFound),
+    };
+
+    if request.method != "POST" {
+        return Err(HttpError::MethodNotAllowed);
+    }
+
+    let params = serde_json::from_slice::<Value>(&request.body)
+        .map_err(|e| ProtocolError::InvalidJson(e.to_string()))?;
+
+    handler(&params, timeout)
+}
+
+// `{"board": ...}`, the solution or a timeout
+fn solve(params: &Value, timeout: Duration) -> Result<Value, HttpError> {
+    let game = read_board(params)?;
+
+    search(Solver::new(game), Arc::new(AtomicBool::new(false)), timeout)
+}
+
+// the search runs on its own thread, `cancel` is raised once `timeout` has passed
+fn search(solver: Solver, cancel: Arc<AtomicBool>, timeout: Duration) -> Result<Value, HttpError> {
+    let solver = solver.with_cancel(cancel.clone());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let _ = sender.send(solver.solve());
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(SolveOutcome::Cancelled) => Err(HttpError::Timeout(timeout)),
+        Ok(outcome) => Ok(json!(Solution::new(outcome.into_sets()))),
+        Err(RecvTimeoutError::Timeout) => {
+            cancel.store(true, Ordering::Relaxed);
+            Err(HttpError::Timeout(timeout))
+        }
+        // the solver thread panicked before it sent a result
+        Err(RecvTimeoutError::Disconnected) => Err(HttpError::Panic),
+    }
+}
+
+// `{"board": ...}`, whether every set on the table is a run or a group
+fn validate(params: &Value, _: Duration) -> Result<Value, HttpError> {
+    let game = read_board(params)?;
+    let invalid_sets = game
+        .board
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, set)| !set.is_empty() && !Game::is_valid_set(set))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    Ok(json!({
+        "valid": invalid_sets.is_empty(),
+        "invalid_sets": invalid_sets,
+        "board": game,
+    }))
+}
+
+// `{"command": ..., "board": ...}`, what the command does to the board, an empty one if missing
+fn parse(params: &Value, _: Duration) -> Result<Value, HttpError> {
+    let command = params
+        .get("command")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ProtocolError::InvalidRequest("'command' must be a string".to_string()))?;
+    let mut game = match params.get("board") {
+        Some(_) => read_board(params)?,
+        None => Game::new(),
+    };
+
+    let applied = match apply_input(&mut game, command).map_err(ProtocolError::from)? {
+        Applied::Operations(operations) => json!({ "operations": operations }),
+        Applied::Player(command) => json!({ "player_command": command }),
+    };
+
+    Ok(json!({ "applied": applied, "board": game }))
+}
+
+fn write_response(stream: &mut impl Write, result: Result<Value, HttpError>) -> io::Result<()> {
+    let (status, body) = match result {
+        Ok(body) => (200, body),
+        Err(e) => (e.status(), e.to_json()),
+    };
+
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    let body = body.to_string();
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn post(path: &str, body: Value) -> Result<Value, HttpError> {
+        let request = Request {
+            method: "POST".to_string(),
+            path: path.to_string(),
+            body: body.to_string().into_bytes(),
+        };
+
+        route(&request, TIMEOUT)
+    }
+
+    #[test]
+    fn test_read_request() {
+        let input =
+            "POST /solve?x=1 HTTP/1.1\r\nHost: localhost\r\ncontent-length: 4\r\n\r\n{}\r\nextra";
+        let request = read_request(&mut input.as_bytes()).unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/solve");
+        assert_eq!(request.body, b"{}\r\n");
+
+        let too_large = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY + 1
+        );
+        let long_head = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_HEAD));
+
+        for (input, status) in [
+            ("POST /solve HTTP/1.1\r\n\r\n", 411),
+            (too_large.as_str(), 413),
+            (long_head.as_str(), 413),
+            ("GET\r\n\r\n", 400),
+            ("GET / HTTP/1.1\r\n", 400),
+        ] {
+            let e = read_request(&mut input.as_bytes()).unwrap_err();
+            assert_eq!(e.status(), status, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_routes() {
+        let solution = post("/solve", json!({ "board": "r3 r4 r5; b10 h10 o10" })).unwrap();
+        assert_eq!(solution["solved"], true);
+
+        let result = post("/validate", json!({ "board": "; r3 r4 r5; b10 h10" })).unwrap();
+        assert_eq!(result["valid"], false);
+        assert_eq!(result["invalid_sets"], json!([2]));
+
+        let result = post("/parse", json!({ "command": "hand r3 w" })).unwrap();
+        assert!(!result["applied"]["operations"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+        assert_eq!(result["board"]["board"][0].as_array().unwrap().len(), 2);
+
+        let e = post(
+            "/parse",
+            json!({ "command": "f(3,4,5)r", "board": "r3 r4" }),
+        )
+        .unwrap_err();
+        assert_eq!(e.status(), 422);
+        assert_eq!(e.to_json()["error"]["code"], "not_in_row");
+
+        let e = post("/solve", json!({ "board": "r3 x" })).unwrap_err();
+        assert_eq!(e.status(), 422);
+
+        assert_eq!(post("/solve", json!({})).unwrap_err().status(), 400);
+        assert_eq!(post("/deal", json!({})).unwrap_err().status(), 404);
+
+        let request = Request {
+            method: "GET".to_string(),
+            path: "/solve".to_string(),
+            body: Vec::new(),
+        };
+        assert_eq!(route(&request, TIMEOUT).unwrap_err().status(), 405);
+    }
+
+    #[test]
+    fn test_timeout() {
+        // a flag raised before the search starts, as the timeout would raise it
+        let game = "r3 r4 r5 b1".parse::<Game>().unwrap();
+        let cancel = Arc::new(AtomicBool::new(true));
+        let e = search(Solver::new(game), cancel, TIMEOUT).unwrap_err();
+
+        assert_eq!(e.status(), 503);
+        assert_eq!(e.to_json()["error"]["code"], "timeout");
+    }
+
+    #[test]
+    fn test_panic() {
+        let e = catch_panic(|| panic!("handler failed")).unwrap_err();
+        assert_eq!(e.status(), 500);
+        assert_eq!(e.to_json()["error"]["code"], "internal");
+
+        let active = Arc::new(AtomicUsize::new(0));
+        let slots = (0..MAX_CONNECTIONS)
+            .map(|_| Slot::acquire(&active).unwrap())
+            .collect::<Vec<_>>();
+        assert!(Slot::acquire(&active).is_none());
+
+        // a thread that panics still gives its slot back
+        let mut slots = slots.into_iter();
+        let slot = slots.next().unwrap();
+        let handle = thread::spawn(move || {
+            let _slot = slot;
+            panic!("handler failed");
+        });
+        assert!(handle.join().is_err());
+        assert!(Slot::acquire(&active).is_some());
+        assert_eq!(active.load(Ordering::SeqCst), MAX_CONNECTIONS - 1);
+    }
+
+    #[test]
+    fn test_serve() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+
+        thread::spawn(move || run(listener, TIMEOUT));
+
+        let body = json!({ "board": "r3 r4 r5" }).to_string();
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "POST /solve HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.ends_with("\"solved\":true}"), "{}", response);
+    }
+}
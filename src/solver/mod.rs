@@ -1,22 +1,141 @@
-use crate::game::{tile::Tile, Game};
+use crate::game::json::{validate_sets, JsonError};
+use crate::game::{tile::Tile, tile_color::TileColor, Game};
+use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
-type CacheKey = Vec<(u8, u8, bool)>;
+type CacheKey = Vec<(u8, u8, bool, bool)>;
 
+// reading the clock on every search node would slow the search down, so the deadline is
+// only checked once per this many nodes
+const DEADLINE_INTERVAL: usize = 1024;
+
+/// The result of a solve in the form written to JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Solution {
+    pub solved: bool,
+    pub sets: Vec<Vec<Tile>>,
+}
+
+impl Solution {
+    pub fn new(result: Option<Vec<Vec<Tile>>>) -> Self {
+        Solution {
+            solved: result.is_some(),
+            sets: result.unwrap_or_default(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, JsonError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(input: &str) -> Result<Self, JsonError> {
+        let solution: Solution = serde_json::from_str(input)?;
+        validate_sets(&solution.sets)?;
+
+        Ok(solution)
+    }
+}
+
+/// How a search ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveOutcome {
+    /// Valid sets that use every tile.
+    Solved(Vec<Vec<Tile>>),
+    /// The whole search ran without finding an arrangement.
+    NotSolved,
+    /// The cancel flag was raised before the search ended.
+    Cancelled,
+}
+
+impl SolveOutcome {
+    /// The sets of a solved game, `None` if it was not solved or the search was cancelled.
+    pub fn into_sets(self) -> Option<Vec<Vec<Tile>>> {
+        match self {
+            SolveOutcome::Solved(sets) => Some(sets),
+            _ => None,
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        matches!(self, SolveOutcome::Solved(_))
+    }
+}
+
+/// Depth-first search for an arrangement of every tile of the game, hand and table, into valid sets.
 pub struct Solver {
     game: Game,
+    cancel: Option<Arc<AtomicBool>>,
+    deadline: Option<Instant>,
+    nodes: Option<Arc<AtomicUsize>>,
+    // every search node counts up, the clock is read once per `DEADLINE_INTERVAL` of them
+    deadline_checks: AtomicUsize,
+    expired: AtomicBool,
 }
 
 impl Solver {
     pub fn new(game: Game) -> Self {
-        Solver { game }
+        Solver {
+            game,
+            cancel: None,
+            deadline: None,
+            nodes: None,
+            deadline_checks: AtomicUsize::new(0),
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    /// Stop the search as soon as `cancel` is set, `solve` then returns `SolveOutcome::Cancelled`.
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Stop the search once `deadline` has passed, as if the cancel flag was set.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Count every search node in `nodes`, so another thread can show the progress.
+    pub fn with_node_count(mut self, nodes: Arc<AtomicUsize>) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+            || self.is_expired()
+    }
+
+    fn is_expired(&self) -> bool {
+        let Some(deadline) = self.deadline else {
+            return false;
+        };
+
+        let checks = self.deadline_checks.fetch_add(1, Ordering::Relaxed);
+
+        if checks.is_multiple_of(DEADLINE_INTERVAL) && Instant::now() >= deadline {
+            self.expired.store(true, Ordering::Relaxed);
+        }
+
+        self.expired.load(Ordering::Relaxed)
     }
 
     fn get_cache_key(tiles: &BTreeMap<Tile, u8>) -> CacheKey {
         let mut key = Vec::new();
         for (tile, &count) in tiles {
             for _ in 0..count {
-                key.push((tile.number, tile.color.to_rank() as u8, tile.is_wildcard));
+                key.push((
+                    tile.number,
+                    tile.color.to_rank() as u8,
+                    tile.is_wildcard,
+                    tile.is_pinned,
+                ));
             }
         }
         // key is already somewhat sorted because BTreeMap is sorted by Tile
@@ -33,6 +152,14 @@ impl Solver {
             return Some(solution_set);
         }
 
+        if self.is_cancelled() {
+            return None;
+        }
+
+        if let Some(nodes) = &self.nodes {
+            nodes.fetch_add(1, Ordering::Relaxed);
+        }
+
         let key = Self::get_cache_key(&tiles);
         if cache.contains(&key) {
             return None;
@@ -56,22 +183,33 @@ impl Solver {
 
         let candidates = self.find_candidates(&first_tile, &tiles, wildcard_count);
 
-        for candidate in candidates {
+        for mut candidate in candidates {
             let mut next_tiles = tiles.clone();
             let mut possible = true;
 
             // The first_tile is already removed from 'tiles' (the count was decremented)
             // But 'candidate' includes 'first_tile'.
             // So we only need to remove the OTHER tiles in the candidate.
-            for (i, c_tile) in candidate.iter().enumerate() {
-                if i == 0 && c_tile == &first_tile {
+            for (i, c_tile) in candidate.iter_mut().enumerate() {
+                if i == 0 && *c_tile == first_tile {
                     // This is our 'first_tile', already accounted for.
                     continue;
                 }
-                
-                // If it's a wildcard we generated, we need to find A wildcard in next_tiles
+
+                // If it's a wildcard we generated, a joker pinned to exactly this tile is used
+                // first, otherwise any free wildcard in next_tiles
                 let target = if c_tile.is_wildcard {
-                    next_tiles.keys().find(|t| t.is_wildcard && next_tiles[t] > 0).cloned()
+                    let pinned = Tile::new_pinned(c_tile.number, c_tile.color);
+
+                    if next_tiles.get(&pinned).copied().unwrap_or(0) > 0 {
+                        *c_tile = pinned.clone();
+                        Some(pinned)
+                    } else {
+                        next_tiles
+                            .keys()
+                            .find(|t| t.is_free_wildcard() && next_tiles[t] > 0)
+                            .cloned()
+                    }
                 } else {
                     (next_tiles.get(c_tile).copied().unwrap_or(0) > 0).then(|| c_tile.clone())
                 };
@@ -108,7 +246,8 @@ impl Solver {
     ) -> Vec<Vec<Tile>> {
         let mut candidates = Vec::new();
 
-        if tile.is_wildcard {
+        // a pinned joker is placed like the tile it stands in for
+        if tile.is_free_wildcard() {
             return Vec::new();
         }
 
@@ -125,7 +264,7 @@ impl Solver {
         total_wildcards: usize,
     ) -> Vec<Vec<Tile>> {
         let mut runs = Vec::new();
-        
+
         let same_color_tiles: Vec<Tile> = others
             .iter()
             .filter(|(t, &c)| t.color == tile.color && !t.is_wildcard && c > 0)
@@ -215,18 +354,31 @@ impl Solver {
         groups
     }
 
-    pub fn solve(&self) -> Option<Vec<Vec<Tile>>> {
+    /// The valid sets that use every tile, if there is such an arrangement.
+    pub fn solve(&self) -> SolveOutcome {
         let board = self.game.get_board();
         let mut tiles_map = BTreeMap::new();
         for tile in board.into_iter().flatten() {
+            // a guessed value does not bind a joker, only a pinned one does
+            let tile = if tile.is_free_wildcard() {
+                Tile::new(251, TileColor::Red, true)
+            } else {
+                tile
+            };
+
             *tiles_map.entry(tile).or_insert(0) += 1;
         }
 
         if tiles_map.is_empty() {
-            return Some(Vec::new());
+            return SolveOutcome::Solved(Vec::new());
         }
 
-        self.search(tiles_map, Vec::new(), &mut HashSet::new())
+        match self.search(tiles_map, Vec::new(), &mut HashSet::new()) {
+            Some(sets) => SolveOutcome::Solved(sets),
+            // the search gives up on every branch once the flag is set
+            None if self.is_cancelled() => SolveOutcome::Cancelled,
+            None => SolveOutcome::NotSolved,
+        }
     }
 }
 
@@ -253,10 +405,11 @@ mod tests {
                 Tile::new(6, TileColor::Orange, false),
             ],
             None,
-        ));
+        ))
+        .unwrap();
 
         let solver = Solver::new(game);
-        assert!(solver.solve().is_none());
+        assert_eq!(solver.solve(), SolveOutcome::NotSolved);
     }
 
     #[test]
@@ -277,10 +430,60 @@ mod tests {
                 Tile::new(6, TileColor::Orange, false),
             ],
             None,
-        ));
+        ))
+        .unwrap();
 
         let solver = Solver::new(game);
-        assert!(solver.solve().is_some());
+        assert!(solver.solve().is_solved());
+    }
+
+    #[test]
+    fn test_solution_json() {
+        let game = Game::new_with_board(vec![vec![
+            Tile::new(4, TileColor::Orange, false),
+            Tile::new(5, TileColor::Orange, false),
+            Tile::new(251, TileColor::Red, true),
+        ]]);
+
+        let solution = Solution::new(Solver::new(game).solve().into_sets());
+        assert!(solution.solved);
+        assert!(solution.sets[0]
+            .iter()
+            .any(|t| t.is_wildcard && t.number != 251));
+
+        let json = solution.to_json().unwrap();
+        assert_eq!(Solution::from_json(&json).unwrap(), solution);
+
+        let unsolved = Solution::new(None).to_json().unwrap();
+        assert_eq!(unsolved, r#"{"solved":false,"sets":[]}"#);
+    }
+
+    #[test]
+    fn test_pinned_wildcard() {
+        let hand = |joker: Tile| {
+            Game::new_with_board(vec![vec![
+                Tile::new(5, TileColor::Red, false),
+                Tile::new(6, TileColor::Red, false),
+                joker,
+            ]])
+        };
+
+        // a joker pinned to red 9 cannot complete 5, 6
+        assert_eq!(
+            Solver::new(hand(Tile::new_pinned(9, TileColor::Red))).solve(),
+            SolveOutcome::NotSolved
+        );
+
+        let sets = Solver::new(hand(Tile::new_pinned(7, TileColor::Red)))
+            .solve()
+            .into_sets()
+            .expect("should solve");
+        assert!(sets[0].contains(&Tile::new_pinned(7, TileColor::Red)));
+
+        // a guessed value does not bind the joker
+        assert!(Solver::new(hand(Tile::new(9, TileColor::Red, true)))
+            .solve()
+            .is_solved());
     }
 
     #[test]
@@ -420,6 +623,43 @@ mod tests {
         board.extend(game_board.clone());
 
         let solver = Solver::new(Game::new_with_board(board));
-        assert!(solver.solve().is_some());
+        assert!(solver.solve().is_solved());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let board = vec![vec![
+            Tile::new(3, TileColor::Red, false),
+            Tile::new(4, TileColor::Red, false),
+            Tile::new(5, TileColor::Red, false),
+        ]];
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let solver = Solver::new(Game::new_with_board(board)).with_cancel(cancel.clone());
+        assert!(solver.solve().is_solved());
+
+        cancel.store(true, Ordering::Relaxed);
+        assert_eq!(solver.solve(), SolveOutcome::Cancelled);
+
+        let board = solver.game.board.clone();
+        let solver = Solver::new(Game::new_with_board(board)).with_deadline(Instant::now());
+        assert_eq!(solver.solve(), SolveOutcome::Cancelled);
+    }
+
+    #[test]
+    fn test_node_count() {
+        let board = vec![vec![
+            Tile::new(3, TileColor::Red, false),
+            Tile::new(4, TileColor::Red, false),
+            Tile::new(5, TileColor::Red, false),
+            Tile::new(10, TileColor::Blue, false),
+            Tile::new(10, TileColor::Black, false),
+            Tile::new(10, TileColor::Orange, false),
+        ]];
+        let nodes = Arc::new(AtomicUsize::new(0));
+
+        let solver = Solver::new(Game::new_with_board(board)).with_node_count(nodes.clone());
+        assert!(solver.solve().is_solved());
+        assert!(nodes.load(Ordering::Relaxed) >= 2);
     }
 }
@@ -0,0 +1,142 @@
+// "Index 12: " in front of every set
+pub const LABEL_WIDTH: u16 = 11;
+const COLUMN_GAP: u16 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileStyle {
+    /// A box of 8x2 characters around the number.
+    Boxed,
+    /// The number on a single line, for terminals that are too small for boxes.
+    Compact,
+}
+
+impl TileStyle {
+    pub fn width(self) -> u16 {
+        match self {
+            TileStyle::Boxed => 8,
+            TileStyle::Compact => 4,
+        }
+    }
+
+    /// The rows of one line of tiles.
+    pub fn height(self) -> u16 {
+        match self {
+            TileStyle::Boxed => 3,
+            TileStyle::Compact => 1,
+        }
+    }
+
+    /// The row of a line of tiles the set label is printed on.
+    pub fn label_row(self) -> u16 {
+        self.height() / 2
+    }
+
+    /// Whether a set of `len` tiles fits on one line of a terminal `width` columns wide.
+    pub fn fits(self, len: usize, width: u16) -> bool {
+        LABEL_WIDTH as usize + len * self.width() as usize <= width as usize
+    }
+
+    /// How many tiles fit on one line of `width` columns, at least one.
+    pub fn per_line(self, width: u16) -> usize {
+        (width / self.width()).max(1) as usize
+    }
+}
+
+/// Where a set of the board is drawn, relative to the top left corner of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placement {
+    pub index: usize,
+    pub x: u16,
+    pub y: u16,
+    /// Longer sets wrap onto the next line.
+    pub per_line: usize,
+}
+
+/// The sets of a board flowed into as many equally wide columns as fit the terminal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub placements: Vec<Placement>,
+    pub height: u16,
+}
+
+impl Layout {
+    /// Lay out the sets given as `(index, length)` in `width` columns.
+    pub fn new(sets: &[(usize, usize)], width: u16, style: TileStyle) -> Self {
+        let longest = sets.iter().map(|(_, len)| *len).max().unwrap_or(1);
+        let per_line = longest.clamp(1, style.per_line(width.saturating_sub(LABEL_WIDTH)));
+        let column_width = LABEL_WIDTH + per_line as u16 * style.width() + COLUMN_GAP;
+        let columns = (width.saturating_add(COLUMN_GAP) / column_width).max(1) as usize;
+
+        let mut placements = Vec::with_capacity(sets.len());
+        let mut y = 0;
+
+        for row in sets.chunks(columns) {
+            let mut lines = 1;
+
+            for (column, (index, len)) in row.iter().enumerate() {
+                placements.push(Placement {
+                    index: *index,
+                    x: column as u16 * column_width,
+                    y,
+                    per_line,
+                });
+                lines = lines.max(len.div_ceil(per_line));
+            }
+
+            y += lines as u16 * style.height();
+        }
+
+        Layout {
+            placements,
+            height: y,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns() {
+        let sets = [(1, 3), (2, 4), (4, 3), (5, 3)];
+
+        // 11 + 4 * 8 + 1 = 44 columns per set
+        let layout = Layout::new(&sets, 100, TileStyle::Boxed);
+        let positions = layout
+            .placements
+            .iter()
+            .map(|p| (p.index, p.x, p.y))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            vec![(1, 0, 0), (2, 44, 0), (4, 0, 3), (5, 44, 3)]
+        );
+        assert_eq!(layout.height, 6);
+
+        let layout = Layout::new(&sets, 200, TileStyle::Boxed);
+        assert_eq!(layout.placements[3].x, 132);
+        assert_eq!(layout.height, 3);
+
+        // 11 + 4 * 4 + 1 = 28 columns per set
+        let layout = Layout::new(&sets, 60, TileStyle::Compact);
+        assert_eq!(layout.placements[1].x, 28);
+        assert_eq!(layout.height, 2);
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert!(TileStyle::Boxed.fits(5, 51));
+        assert!(!TileStyle::Boxed.fits(13, 100));
+        assert!(TileStyle::Compact.fits(13, 100));
+
+        // 13 tiles in 40 columns leave room for 7 compact tiles per line
+        let layout = Layout::new(&[(1, 13), (2, 3)], 40, TileStyle::Compact);
+        assert_eq!(layout.placements[0].per_line, 7);
+        assert_eq!(layout.placements[1].y, 2);
+        assert_eq!(layout.height, 3);
+
+        let layout = Layout::new(&[], 40, TileStyle::Boxed);
+        assert_eq!(layout.height, 0);
+    }
+}
@@ -1,19 +1,38 @@
 use crossterm::{
     cursor,
     event::{self, Event, KeyCode},
-    style::{ResetColor, SetForegroundColor},
+    style::{Color, ResetColor, SetForegroundColor},
     terminal::{self, ClearType},
     Command, ExecutableCommand,
 };
 
-use crate::game::{
-    parser::{command_capture_to_tile_command, commands_capture_to_tile_commands, Parser},
+use layout::{Layout, TileStyle, LABEL_WIDTH};
+use rummy_app::game::{
+    input::execute_input,
+    journal::Journal,
+    parser::{commands_to_tile_commands, parse_init, ParseError},
     tile::Tile,
-    Game, ToTiles,
+    tile_color::TileColor,
+    tile_command::TileCommandError,
+    Game, GameOperation,
 };
-use crate::solver::Solver;
+use rummy_app::solver::{SolveOutcome, Solver};
 use std::io::{stdout, Result as ioResult, Stdout, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use viewport::Viewport;
 
+mod layout;
+mod viewport;
+
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+// how often the solver page redraws while the search runs
+const SOLVE_REFRESH: Duration = Duration::from_millis(100);
+
+#[allow(clippy::upper_case_acronyms)]
 pub struct TUI {
     output: Stdout,
     buffer: String,
@@ -21,18 +40,55 @@ pub struct TUI {
     page: Page,
     prev_page: Page,
     game: Game,
+    journal: Journal,
+    syntax_errors: Vec<ParseError>,
+    solve_job: Option<SolveJob>,
+    viewport: Viewport,
+    in_viewport: bool,
+    hidden: bool,
+    size: (u16, u16),
+    tile_style: TileStyle,
+}
+
+// a search running on its own thread for the solver page
+struct SolveJob {
+    receiver: Receiver<SolveOutcome>,
+    cancel: Arc<AtomicBool>,
+    nodes: Arc<AtomicUsize>,
+    started: Instant,
 }
 
+#[derive(Clone, Debug)]
+enum SolveStatus {
+    Running,
+    Done {
+        result: Option<Vec<Vec<Tile>>>,
+        elapsed: Duration,
+        nodes: usize,
+    },
+    Cancelled {
+        elapsed: Duration,
+        nodes: usize,
+    },
+}
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone, Debug)]
 enum Page {
     MainPage,
     GameRulePage,
     GameInitPage,
     GamePage,
-    SolverPage,
+    SolverPage { status: SolveStatus },
     InvalidCommandPage { error_message: String },
 }
 
+impl Default for TUI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TUI {
     pub fn new() -> Self {
         TUI {
@@ -42,18 +98,29 @@ impl TUI {
             page: Page::MainPage,
             prev_page: Page::MainPage,
             game: Game::new(),
+            journal: Journal::new(),
+            syntax_errors: Vec::new(),
+            solve_job: None,
+            viewport: Viewport::default(),
+            in_viewport: false,
+            hidden: false,
+            size: (80, 24),
+            tile_style: TileStyle::Boxed,
         }
     }
 
     fn reset(&mut self) {
         self.buffer.clear();
         self.y_pos = 0;
+        self.viewport = Viewport::default();
         self.game.reset();
+        self.journal.clear();
     }
 
     pub fn run(&mut self) -> ioResult<()> {
         terminal::enable_raw_mode()?;
         self.execute(terminal::EnterAlternateScreen)?;
+        self.size = terminal::size()?;
 
         loop {
             if self.render()? {
@@ -71,6 +138,8 @@ impl TUI {
     fn render(&mut self) -> ioResult<bool> {
         self.execute(terminal::Clear(ClearType::All))?;
         self.y_pos = 0;
+        // boxed tiles unless this frame turns out too small for them
+        self.tile_style = TileStyle::Boxed;
         let page = self.page.clone();
 
         match page {
@@ -82,7 +151,7 @@ impl TUI {
 
                 let mut should_exit = false;
 
-                if let Event::Key(key) = event::read()? {
+                if let Event::Key(key) = self.read_event()? {
                     match key.code {
                         KeyCode::Char('q') => should_exit = true,
                         KeyCode::Char('s') => {
@@ -102,7 +171,7 @@ impl TUI {
 
                 let mut should_exit = false;
 
-                if let Event::Key(key) = event::read()? {
+                if let Event::Key(key) = self.read_event()? {
                     match key.code {
                         KeyCode::Char('q') => should_exit = true,
                         KeyCode::Char('m') => {
@@ -125,48 +194,55 @@ impl TUI {
 
                 let mut should_exit = false;
 
-                if let Event::Key(key) = event::read()? {
+                if let Event::Key(key) = self.read_event()? {
                     match key.code {
-                        KeyCode::Char('q') => should_exit = true,
-                        KeyCode::Char('m') => {
-                            self.page = Page::MainPage;
-                        }
                         KeyCode::Char(c) => {
-                            self.buffer.push_str(&c.to_string());
+                            self.buffer.push(c);
+                            self.syntax_errors.clear();
                         }
                         KeyCode::Backspace => {
                             self.buffer.pop();
+                            self.syntax_errors.clear();
                         }
                         KeyCode::Enter => {
-                            let command = self.buffer.trim();
-                            let command = Parser::new().parse_init(command);
-
-                            if let Some(commands) = command {
-                                let tile_commands =
-                                    commands_capture_to_tile_commands(commands, &self.game);
-
-                                match tile_commands {
-                                    Ok(tile_commands) => {
-                                        let game_operations = tile_commands
-                                            .into_iter()
-                                            .map(|cmd| cmd.to_tiles())
-                                            .collect::<Vec<_>>();
-
-                                        for operation in game_operations {
-                                            self.game.operate(operation);
+                            let input = self.buffer.trim().to_string();
+                            self.syntax_errors.clear();
+
+                            // 'q' and 'm' are typed like commands so they can start one
+                            if input == "q" {
+                                should_exit = true;
+                            } else if input == "m" {
+                                self.page = Page::MainPage;
+                            } else {
+                                match parse_init(&input) {
+                                    Ok(commands) => {
+                                        let tile_commands =
+                                            commands_to_tile_commands(&commands, &self.game);
+
+                                        match tile_commands {
+                                            Ok(tile_commands) => {
+                                                let game_operations = tile_commands
+                                                    .into_iter()
+                                                    .map(GameOperation::from)
+                                                    .collect();
+
+                                                self.apply_operations(&input, game_operations);
+                                                self.page = Page::GamePage;
+                                            }
+                                            Err(e) => {
+                                                self.display_command_error(e);
+                                            }
                                         }
-
-                                        self.page = Page::GamePage;
                                     }
-                                    Err(e) => {
-                                        self.display_error(&e.to_string());
+                                    Err(errors) => {
+                                        self.display_syntax_errors(errors);
                                     }
                                 }
-                            } else {
-                                self.display_error("Invalid command format!");
                             }
 
-                            self.buffer.clear();
+                            if self.syntax_errors.is_empty() {
+                                self.buffer.clear();
+                            }
                         }
                         _ => {}
                     }
@@ -182,49 +258,45 @@ impl TUI {
 
                 let mut should_exit = false;
 
-                if let Event::Key(key) = event::read()? {
+                if let Event::Key(key) = self.read_event()? {
                     match key.code {
-                        KeyCode::Char('q') => should_exit = true,
-
-                        KeyCode::Char('m') => {
-                            self.page = Page::MainPage;
-                        }
-
                         KeyCode::Char(c) => {
-                            self.buffer.push_str(&c.to_string());
+                            self.buffer.push(c);
+                            self.syntax_errors.clear();
                         }
 
                         KeyCode::Backspace => {
                             self.buffer.pop();
+                            self.syntax_errors.clear();
                         }
 
-                        KeyCode::Enter => {
-                            let command = self.buffer.trim();
-
-                            if command == "solve" {
-                                self.page = Page::SolverPage;
-                            } else {
-                                let command = Parser::new().parse(command);
-
-                                if let Some(cmd) = command {
-                                    let tile_command =
-                                        command_capture_to_tile_command(cmd, &self.game);
+                        KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown => {
+                            self.viewport.handle_key(key.code);
+                        }
 
-                                    match tile_command {
-                                        Ok(tile_command) => {
-                                            let game_operation = tile_command.to_tiles();
-                                            self.game.operate(game_operation);
-                                        }
-                                        Err(e) => {
-                                            self.display_error(&e.to_string());
-                                        }
-                                    }
-                                } else {
-                                    self.display_error("Invalid command format!");
+                        KeyCode::Enter => {
+                            let input = self.buffer.trim().to_string();
+                            self.syntax_errors.clear();
+
+                            if input == "q" {
+                                should_exit = true;
+                            } else if input == "m" {
+                                self.page = Page::MainPage;
+                            } else if input == "solve" {
+                                self.start_solve();
+                            } else if let Some(path) = input.strip_prefix("save ") {
+                                if let Err(e) = self.journal.save(path.trim()) {
+                                    self.display_error(&e.to_string());
                                 }
+                            } else if let Err(e) =
+                                execute_input(&mut self.game, &mut self.journal, &input)
+                            {
+                                self.display_command_error(e);
                             }
 
-                            self.buffer.clear();
+                            if self.syntax_errors.is_empty() {
+                                self.buffer.clear();
+                            }
                         }
                         _ => {}
                     }
@@ -233,23 +305,55 @@ impl TUI {
                 Ok(should_exit)
             }
 
-            Page::SolverPage => {
-                self.render_solver_page()?;
+            Page::SolverPage {
+                status: SolveStatus::Running,
+            } => {
+                if self.finish_solve() {
+                    return Ok(false);
+                }
+
+                self.render_solve_progress()?;
+
+                self.flush()?;
+
+                let mut should_exit = false;
+
+                if event::poll(SOLVE_REFRESH)? {
+                    if let Event::Key(key) = self.read_event()? {
+                        match key.code {
+                            KeyCode::Esc => self.cancel_solve(),
+                            KeyCode::Char('q') => {
+                                self.cancel_solve();
+                                should_exit = true;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+
+                Ok(should_exit)
+            }
+
+            Page::SolverPage { status } => {
+                self.render_solver_page(&status)?;
 
                 self.flush()?;
 
                 let mut should_exit = false;
 
-                if let Event::Key(key) = event::read()? {
+                if let Event::Key(key) = self.read_event()? {
                     match key.code {
                         KeyCode::Char('q') => should_exit = true,
                         KeyCode::Char('m') => {
                             self.page = Page::MainPage;
                         }
                         KeyCode::Char('c') => {
+                            self.viewport.offset = 0;
                             self.page = Page::GamePage;
                         }
-                        _ => {}
+                        code => {
+                            self.viewport.handle_key(code);
+                        }
                     }
                 };
 
@@ -269,7 +373,7 @@ impl TUI {
 
                 let mut should_exit = false;
 
-                if let Event::Key(key) = event::read()? {
+                if let Event::Key(key) = self.read_event()? {
                     match key.code {
                         KeyCode::Char('c') => {
                             self.page = self.prev_page.clone();
@@ -292,7 +396,7 @@ impl TUI {
     fn render_main_page(&mut self) -> ioResult<()> {
         self.draw_box(50, 2, 0, true, "Rummy Solver App")?;
         self.execute_move(0, 1)?;
-        print!("Press 'q' to quit or press 's' to start a game.");
+        self.print("Press 'q' to quit or press 's' to start a game.");
         self.y_pos += 1;
 
         Ok(())
@@ -306,9 +410,62 @@ impl TUI {
         self.print_and_move("    d - Draw a tile from the deck.", 1)?;
         self.print_and_move("    p - put a set of tiles on the table.", 1)?;
         self.print_and_move("    r - Replace the wildcards.", 1)?;
-        self.print_and_move("    solve - Solve the game.", 2)?;
         self.print_and_move(
-            "[color] - The color of the tile can be 'b' - blue, 'r' - red, 'o' - orange, 'h' - black.",
+            "    f - Play a set of tiles from your hand (index 0) onto the table.",
+            1,
+        )?;
+        self.print_and_move("    m - Move tiles from one set to another.", 1)?;
+        self.print_and_move(
+            "    x - Remove tiles from a set, or delete the whole set.",
+            1,
+        )?;
+        self.print_and_move(
+            "    s - Split a set in two.   j - Join (merge) two sets.",
+            1,
+        )?;
+        self.print_and_move("    solve - Solve the game.", 1)?;
+        self.print_and_move(
+            "    q / m - Quit, or return to the main menu (type it and press Enter).",
+            1,
+        )?;
+        self.print_and_move("    [command];[command] - Apply several commands at once, nothing is applied if one fails.", 1)?;
+        self.print_and_move("    hand [tiles] - Add tiles to your hand, e.g. hand r3 r4 b10 w or hand r:3,4,5 b:10.", 1)?;
+        self.print_and_move(
+            "    save [file] - Save every command entered so far to a journal file.",
+            2,
+        )?;
+        self.print_and_move("Turns: ", 1)?;
+        self.print_and_move(
+            "    begin - Start a turn, the table may be invalid until the turn ends.",
+            1,
+        )?;
+        self.print_and_move("    commit - End the turn, rolled back if any set is invalid or a table tile is in your hand.", 1)?;
+        self.print_and_move("    rollback - Undo every command since 'begin'.", 2)?;
+        self.print_and_move("Players (player 0 is you): ", 1)?;
+        self.print_and_move("    join [name] [count] - An opponent joins the game holding [count] tiles (default 14).", 1)?;
+        self.print_and_move(
+            "    next - End the current turn and pass to the next player.",
+            1,
+        )?;
+        self.print_and_move(
+            "    drew [player] [count] - An opponent drew [count] unknown tiles (default 1).",
+            1,
+        )?;
+        self.print_and_move(
+            "    played [player] [count] - An opponent put [count] tiles on the table (default 1).",
+            1,
+        )?;
+        self.print_and_move("    took [player] [color]([number])... - An opponent picked up known tiles, e.g. took 1 r(3)b(4).", 1)?;
+        self.print_and_move(
+            "    meld [player] - The player has made the initial meld.",
+            2,
+        )?;
+        self.print_and_move(
+            "[color] - The color of the tile can be 'b' - blue, 'r' - red, 'o' - orange, 'h' - black, or the full name.",
+            1,
+        )?;
+        self.print_and_move(
+            "Commands ignore case and spaces, e.g. 'A0( 3, 4 ) Red' is the same as 'a0(3,4)r'.",
             2,
         )?;
         self.print_and_move(
@@ -342,10 +499,34 @@ impl TUI {
             "    p(r,b,h)10 - Put a set of tiles with color Red, Blue, Black with number 10 on the table.",
             1,
         )?;
+        self.print_and_move(
+            "    p(5,6,w7)r - Put red 5, 6 and a joker standing in for red 7 ('w=7' works as well, 'wh' pins a joker in a group to black).",
+            1,
+        )?;
+        self.print_and_move(
+            "    p(3-5,w,7)r - Put red 3, 4, 5, a wildcard and red 7 on the table, '3..5' works as well.",
+            1,
+        )?;
         self.print_and_move(
             "    d(10)r - Draw a red tile with number 10 from the deck.",
             1,
         )?;
+        self.print_and_move(
+            "    f(9,10,11)h - Take black 9, 10, 11 out of your hand and put them on the table as a new set.",
+            1,
+        )?;
+        self.print_and_move(
+            "    m3>5(7)r - Move the red 7 from the set with index 3 to the set with index 5, splitting runs as needed.",
+            1,
+        )?;
+        self.print_and_move(
+            "    x0(5)r - Remove a red 5 from your hand.   x3 - Delete the set with index 3, other indices do not change.",
+            1,
+        )?;
+        self.print_and_move(
+            "    s3@4 - Split the set with index 3 after its 4th tile.   j3>5 - Merge the set with index 3 into the set with index 5.",
+            1,
+        )?;
         self.print_and_move(
             "    r1(11,12)h(10,12)b - Replace the wildcards with black tiles with number 11 and 12 to the tile set with index 1.",   1)?;
         self.print_and_move("\tThen, put these wildcards to the blue tiles with number 10 and 12, and put the new tiles to the board.", 2)?;
@@ -359,62 +540,126 @@ impl TUI {
     fn render_game_init_page(&mut self) -> ioResult<()> {
         self.execute_move(0, 0)?;
         self.print_and_move("Please enter your initial tile set. ", 1)?;
-        self.print_and_move("Format: [color]([number]) (e.g. r(3) for red 3)", 1)?;
-        self.print_and_move("Press 'Enter' when done.", 2)?;
+        self.print_and_move("Format: [color][number], [color]([number]) or [color]:[number],* (e.g. r3 r4 b10 w or r:3,4,5 b:10)", 1)?;
+        self.print_and_move(
+            "Press 'Enter' when done, or enter 'q' to quit / 'm' for the main menu.",
+            2,
+        )?;
 
         self.print_and_move(
             format!("Your initial tile set: {}", self.buffer).as_str(),
             0,
         )?;
+        self.print_syntax_errors(23)?;
         self.execute_move(23 + self.buffer.len() as u16, 0)?;
 
         Ok(())
     }
 
     fn render_game_page(&mut self) -> ioResult<()> {
-        self.execute_move(0, 0)?;
+        // the command line, its syntax errors and the scroll status are pinned under the board
+        let footer = 2 + self.syntax_errors.len() as u16 + 1.min(self.syntax_errors.len() as u16);
         let board = self.game.get_board();
+        self.begin_viewport(footer, &board[1..])?;
+
+        self.execute_move(0, 0)?;
         self.print_and_move("Initital Tile Set: ", 1)?;
 
-        for (j, tile) in board[0].iter().enumerate() {
-            self.draw_tile(tile.clone(), 2 + (j * 8) as u16)?;
-        }
+        // the hand wraps instead of making the whole page compact
+        let per_line = self.tile_style.per_line(self.size.0.saturating_sub(2));
+        let lines = board[0].len().div_ceil(per_line).max(1) as u16;
+        self.draw_tiles(&board[0], 2, self.y_pos, per_line)?;
 
-        self.y_pos += 4;
+        self.y_pos += lines * self.tile_style.height() + 1;
+
+        self.print_players()?;
+
+        if self.game.is_turn_started() {
+            self.print_and_move(
+                "Turn in progress - 'commit' to end the turn or 'rollback' to undo it.",
+                2,
+            )?;
+        }
 
         self.print_and_move("Current Board: ", 2)?;
         let board = self.game.get_board();
 
         self.print_board(&board, true)?;
-        self.y_pos += 4;
 
-        self.execute_move(0, 0)?;
+        if self.end_viewport()? {
+            return self.render_game_page();
+        }
+
         self.print_and_move(format!("Your current command: {}", self.buffer).as_str(), 0)?;
+        self.print_syntax_errors(22)?;
         self.execute_move(22 + self.buffer.len() as u16, 0)?;
 
         Ok(())
     }
 
-    fn render_solver_page(&mut self) -> ioResult<()> {
-        let game = self.game.clone();
-        let solver = Solver::new(game);
+    fn render_solve_progress(&mut self) -> ioResult<()> {
+        let Some(job) = &self.solve_job else {
+            return Ok(());
+        };
+
+        let elapsed = job.started.elapsed();
+        let nodes = job.nodes.load(Ordering::Relaxed);
+        let spinner =
+            SPINNER[(elapsed.as_millis() / SOLVE_REFRESH.as_millis()) as usize % SPINNER.len()];
 
-        self.print_and_move("Loading...", 0)?;
+        self.execute_move(0, 0)?;
+        self.print_and_move(
+            format!(
+                "{} Solving... {:.1}s, {} nodes searched",
+                spinner,
+                elapsed.as_secs_f64(),
+                nodes
+            )
+            .as_str(),
+            1,
+        )?;
+        self.print_and_move("Press 'Esc' to cancel, or 'q' to quit.", 1)?;
 
-        let result = solver.solve();
+        Ok(())
+    }
 
+    fn render_solver_page(&mut self, status: &SolveStatus) -> ioResult<()> {
         self.execute_move(0, 0)?;
 
-        if let Some(board) = result {
-            self.print_and_move("Game Solved!", 1)?;
-            self.print_and_move("Press 'c' to continue...", 2)?;
+        match status {
+            SolveStatus::Done {
+                result: Some(board),
+                elapsed,
+                nodes,
+            } => {
+                self.print_and_move("Game Solved!", 1)?;
+                self.print_solve_stats(*elapsed, *nodes)?;
+                self.print_and_move("Press 'c' to continue...", 2)?;
 
-            self.print_and_move("The solution board: ", 2)?;
+                self.print_and_move("The solution board: ", 2)?;
 
-            self.print_board(&board, false)?;
-        } else {
-            self.print_and_move("Game Not Solved!", 1)?;
-            self.print_and_move("Press 'c' to continue...", 2)?;
+                self.begin_viewport(1, board)?;
+                self.print_board(board, false)?;
+
+                if self.end_viewport()? {
+                    return self.render_solver_page(status);
+                }
+            }
+            SolveStatus::Done {
+                result: None,
+                elapsed,
+                nodes,
+            } => {
+                self.print_and_move("Game Not Solved!", 1)?;
+                self.print_solve_stats(*elapsed, *nodes)?;
+                self.print_and_move("Press 'c' to continue...", 2)?;
+            }
+            SolveStatus::Cancelled { elapsed, nodes } => {
+                self.print_and_move("Solve cancelled.", 1)?;
+                self.print_solve_stats(*elapsed, *nodes)?;
+                self.print_and_move("Press 'c' to continue...", 2)?;
+            }
+            SolveStatus::Running => {}
         }
 
         self.execute_move(0, 0)?;
@@ -422,47 +667,252 @@ impl TUI {
         Ok(())
     }
 
-    fn print_board(&mut self, board: &Vec<Vec<Tile>>, skip: bool) -> ioResult<()> {
-        let mut count = 0;
+    // draw the next rows into a viewport that leaves `footer` rows free at the bottom of the terminal,
+    // tiles are compact if one of the `sets` is too long for a line of boxes
+    fn begin_viewport(&mut self, footer: u16, sets: &[Vec<Tile>]) -> ioResult<()> {
+        let (width, rows) = self.size;
+        let longest = sets.iter().map(Vec::len).max().unwrap_or(0);
 
-        for (i, row) in board.iter().enumerate() {
-            if skip && i == 0 {
-                continue;
-            }
+        if !self.tile_style.fits(longest, width) {
+            self.tile_style = TileStyle::Compact;
+        }
+
+        self.viewport.top = self.y_pos;
+        self.viewport.height = rows.saturating_sub(self.y_pos + footer).max(1);
+        self.in_viewport = true;
+
+        Ok(())
+    }
+
+    // print the scroll status under the viewport and move on to the footer rows, returns true
+    // if the page has to be drawn again, compact when boxed tiles overflow or scrolled back
+    // when the content shrank under the offset
+    fn end_viewport(&mut self) -> ioResult<bool> {
+        self.viewport.content = self.y_pos - self.viewport.top;
+        self.in_viewport = false;
+        self.hidden = false;
+
+        let is_overflowing = self.tile_style == TileStyle::Boxed && self.viewport.is_scrollable();
+
+        if is_overflowing {
+            self.tile_style = TileStyle::Compact;
+        }
+
+        if is_overflowing || self.viewport.clamp() {
+            self.execute(terminal::Clear(ClearType::All))?;
+            self.y_pos = 0;
+
+            return Ok(true);
+        }
+
+        self.y_pos = self.viewport.top + self.viewport.height;
+        let status = self.viewport.status();
+        self.print_and_move(&status, 1)?;
+
+        Ok(false)
+    }
+
+    fn print_solve_stats(&mut self, elapsed: Duration, nodes: usize) -> ioResult<()> {
+        self.print_and_move(
+            format!("{:.1}s, {} nodes searched.", elapsed.as_secs_f64(), nodes).as_str(),
+            1,
+        )
+    }
 
-            let x_pos =
-                (board[i - 1].len() * 1.min(count) * 8 + 11 * 1.min(count) + (12) * count) as u16;
+    // search the current board on a background thread, the solver page shows its progress
+    fn start_solve(&mut self) {
+        self.cancel_solve();
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let nodes = Arc::new(AtomicUsize::new(0));
+        let solver = Solver::new(self.game.clone())
+            .with_cancel(cancel.clone())
+            .with_node_count(nodes.clone());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            // the page may have been left already, then nobody is listening
+            let _ = sender.send(solver.solve());
+        });
+
+        self.solve_job = Some(SolveJob {
+            receiver,
+            cancel,
+            nodes,
+            started: Instant::now(),
+        });
+        self.viewport.offset = 0;
+        self.page = Page::SolverPage {
+            status: SolveStatus::Running,
+        };
+    }
+
+    // store the result in the page once the search is done, returns true if it is
+    fn finish_solve(&mut self) -> bool {
+        let Some(job) = &self.solve_job else {
+            return false;
+        };
 
-            self.execute(cursor::MoveTo(x_pos, self.y_pos + 1))?;
-            print!("Index {}: ", i);
-            self.execute_move(0, 0)?;
-            for (j, tile) in row.iter().enumerate() {
-                self.draw_tile(tile.clone(), x_pos + 11 + (j * 8) as u16)?;
+        let outcome = match job.receiver.try_recv() {
+            Ok(outcome) => outcome,
+            Err(TryRecvError::Empty) => return false,
+            // the result is only lost if the solver thread panicked
+            Err(TryRecvError::Disconnected) => {
+                self.solve_job = None;
+                self.page = Page::GamePage;
+                self.display_error("The solver stopped unexpectedly, the game was not solved!");
+
+                return true;
             }
+        };
+
+        let elapsed = job.started.elapsed();
+        let nodes = job.nodes.load(Ordering::Relaxed);
+
+        self.page = Page::SolverPage {
+            status: match outcome {
+                SolveOutcome::Cancelled => SolveStatus::Cancelled { elapsed, nodes },
+                outcome => SolveStatus::Done {
+                    result: outcome.into_sets(),
+                    elapsed,
+                    nodes,
+                },
+            },
+        };
+        self.solve_job = None;
+
+        true
+    }
+
+    fn cancel_solve(&mut self) {
+        if let Some(job) = self.solve_job.take() {
+            job.cancel.store(true, Ordering::Relaxed);
+
+            self.page = Page::SolverPage {
+                status: SolveStatus::Cancelled {
+                    elapsed: job.started.elapsed(),
+                    nodes: job.nodes.load(Ordering::Relaxed),
+                },
+            };
+        }
+    }
+
+    fn print_players(&mut self) -> ioResult<()> {
+        if self.game.players.len() < 2 {
+            return Ok(());
+        }
 
-            count += 1;
+        self.print_and_move("Players: ", 1)?;
 
-            if board[i - 1].len() > 5 || count > 1 {
-                self.y_pos += 3;
-                count = 0;
+        for (i, player) in self.game.players.clone().iter().enumerate() {
+            let turn = if i == self.game.current_turn {
+                ">"
+            } else {
+                " "
+            };
+            let meld = if player.has_initial_meld {
+                "melded"
+            } else {
+                "no initial meld"
+            };
+
+            self.print_and_move(
+                format!(
+                    "  {} {}: {} - {} tiles, {}",
+                    turn,
+                    i,
+                    player.name,
+                    self.game.tile_count(i).unwrap_or_default(),
+                    meld
+                )
+                .as_str(),
+                0,
+            )?;
+
+            if !player.known_tiles.is_empty() {
+                self.print(", known: ");
+
+                for tile in &player.known_tiles {
+                    self.execute(SetForegroundColor(as_color(tile.color)))?;
+                    if tile.is_wildcard {
+                        self.print("w ");
+                    } else {
+                        self.print(&format!("{} ", tile.number));
+                    }
+                    self.execute(ResetColor)?;
+                }
             }
+
+            self.execute_move(0, 1)?;
         }
 
-        self.y_pos += 4;
+        self.execute_move(0, 1)?;
+
+        Ok(())
+    }
+
+    fn print_board(&mut self, board: &[Vec<Tile>], skip: bool) -> ioResult<()> {
+        // deleted sets leave an empty row behind so the other indices stay the same
+        let sets = board
+            .iter()
+            .enumerate()
+            .filter(|(i, row)| (!skip || *i > 0) && !row.is_empty())
+            .map(|(i, row)| (i, row.len()))
+            .collect::<Vec<_>>();
+        let layout = Layout::new(&sets, self.size.0, self.tile_style);
+        let y_pos = self.y_pos;
+
+        for placement in &layout.placements {
+            self.move_to(
+                placement.x,
+                y_pos + placement.y + self.tile_style.label_row(),
+            )?;
+            self.print(&format!("Index {}: ", placement.index));
+            self.draw_tiles(
+                &board[placement.index],
+                placement.x + LABEL_WIDTH,
+                y_pos + placement.y,
+                placement.per_line,
+            )?;
+        }
+
+        self.y_pos = y_pos + layout.height + 1;
 
         Ok(())
     }
 
     fn print_and_move(&mut self, text: &str, offset: u16) -> ioResult<()> {
         self.execute_move(0, 0)?;
-        print!("{}", text);
+        self.print(text);
         self.execute_move(0, offset)?;
         Ok(())
     }
 
     fn execute_move(&mut self, x_pos: u16, y_offset: u16) -> ioResult<()> {
         self.y_pos += y_offset;
-        self.execute(cursor::MoveTo(x_pos, self.y_pos))
+        self.move_to(x_pos, self.y_pos)
+    }
+
+    // rows inside the viewport are shifted by its offset, text on hidden rows is not printed
+    fn move_to(&mut self, x_pos: u16, y_pos: u16) -> ioResult<()> {
+        let row = if self.in_viewport {
+            self.viewport.row(y_pos)
+        } else {
+            Some(y_pos)
+        };
+
+        self.hidden = row.is_none();
+
+        match row {
+            Some(row) => self.execute(cursor::MoveTo(x_pos, row)),
+            None => Ok(()),
+        }
+    }
+
+    fn print(&mut self, text: &str) {
+        if !self.hidden {
+            print!("{}", text);
+        }
     }
 
     fn execute(&mut self, command: impl Command) -> ioResult<()> {
@@ -471,11 +921,22 @@ impl TUI {
         Ok(())
     }
 
+    // a resized terminal is drawn again with the next render, laid out for its new size
+    fn read_event(&mut self) -> ioResult<Event> {
+        let event = event::read()?;
+
+        if let Event::Resize(width, height) = event {
+            self.size = (width, height);
+        }
+
+        Ok(event)
+    }
+
     fn flush(&mut self) -> ioResult<()> {
         self.output.flush()
     }
 
-    fn display_error(self: &mut Self, error: &str) {
+    fn display_error(&mut self, error: &str) {
         self.prev_page = self.page.clone();
         self.page = Page::InvalidCommandPage {
             error_message: error.to_string(),
@@ -483,8 +944,69 @@ impl TUI {
         self.buffer.clear();
     }
 
-    fn generate_text_middle(self: &Self, width: u16, text: &str) -> String {
-        let padding = (width as usize - 2 - text.len()).max(0);
+    // syntax errors keep the input so it can be fixed, the errors are shown under it
+    fn display_syntax_errors(&mut self, errors: Vec<ParseError>) {
+        let leading = self
+            .buffer
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .count();
+        self.syntax_errors = errors.into_iter().map(|e| e.shifted(leading)).collect();
+    }
+
+    fn display_command_error(&mut self, error: TileCommandError) {
+        match error {
+            TileCommandError::Syntax(errors) => self.display_syntax_errors(errors),
+            e => self.display_error(&e.to_string()),
+        }
+    }
+
+    // print a caret under every offending column of the input line and the messages below,
+    // `x_pos` is where the input starts
+    fn print_syntax_errors(&mut self, x_pos: u16) -> ioResult<()> {
+        if self.syntax_errors.is_empty() {
+            return Ok(());
+        }
+
+        let y_pos = self.y_pos;
+        let errors = self.syntax_errors.clone();
+
+        for error in &errors {
+            self.move_to(x_pos + error.column as u16, y_pos + 1)?;
+            self.print("^");
+        }
+
+        self.y_pos += 1;
+
+        for error in &errors {
+            self.execute_move(x_pos, 1)?;
+            self.print(&error.to_string());
+        }
+
+        self.y_pos = y_pos;
+        self.execute_move(0, 0)?;
+
+        Ok(())
+    }
+
+    // apply the tiles of the init page and record them once they all succeeded, a failing one
+    // leaves the game and the journal unchanged
+    fn apply_operations(&mut self, input: &str, game_operations: Vec<GameOperation>) {
+        let mut game = self.game.clone();
+
+        for game_operation in game_operations.clone() {
+            if let Err(e) = game.operate(game_operation) {
+                self.display_error(&e.to_string());
+                return;
+            }
+        }
+
+        self.game = game;
+        self.journal.record(input, game_operations);
+    }
+
+    fn generate_text_middle(&self, width: u16, text: &str) -> String {
+        let padding = (width as usize).saturating_sub(2 + text.len());
         let left_padding = padding / 2;
         let right_padding = padding - left_padding;
         format!(
@@ -496,7 +1018,7 @@ impl TUI {
     }
 
     fn draw_box(
-        self: &mut Self,
+        &mut self,
         width: u16,
         height: u16,
         x_pos: u16,
@@ -508,21 +1030,21 @@ impl TUI {
         let text_middle = self.generate_text_middle(width, text);
         let bottom = format!("└{}┘", "─".repeat((width - 2) as usize));
 
-        self.execute(cursor::MoveTo(x_pos, self.y_pos))?;
+        self.move_to(x_pos, self.y_pos)?;
 
         let cursor_y = self.y_pos;
 
-        print!("{}", top);
+        self.print(&top);
         for i in 1..=height {
-            self.execute(cursor::MoveTo(x_pos, cursor_y + i))?;
+            self.move_to(x_pos, cursor_y + i)?;
             if i == (height >> 1) {
-                print!("{}", text_middle);
+                self.print(&text_middle);
             } else {
-                print!("{}", blank_middle);
+                self.print(&blank_middle);
             }
         }
-        self.execute(cursor::MoveTo(x_pos, cursor_y + height))?;
-        print!("{}", bottom);
+        self.move_to(x_pos, cursor_y + height)?;
+        self.print(&bottom);
 
         self.flush()?;
 
@@ -533,16 +1055,67 @@ impl TUI {
         Ok(())
     }
 
-    fn draw_tile(self: &mut Self, tile: Tile, x_pos: u16) -> ioResult<()> {
-        self.execute(SetForegroundColor(tile.color.as_color()))?;
-        let text = if tile.is_wildcard {
-            format!("w {}", tile.number)
-        } else {
-            format!("{}", tile.number)
-        };
-        self.draw_box(8, 2, x_pos, false, text.as_str())?;
+    // `per_line` tiles on a line, the next ones wrap below
+    fn draw_tiles(
+        &mut self,
+        tiles: &[Tile],
+        x_pos: u16,
+        y_pos: u16,
+        per_line: usize,
+    ) -> ioResult<()> {
+        for (j, tile) in tiles.iter().enumerate() {
+            let x = x_pos + (j % per_line) as u16 * self.tile_style.width();
+            let y = y_pos + (j / per_line) as u16 * self.tile_style.height();
+
+            self.draw_tile(tile, x, y)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_tile(&mut self, tile: &Tile, x_pos: u16, y_pos: u16) -> ioResult<()> {
+        self.execute(SetForegroundColor(as_color(tile.color)))?;
+
+        match self.tile_style {
+            TileStyle::Boxed => {
+                let text = if tile.is_wildcard {
+                    format!("w {}", tile.number)
+                } else {
+                    format!("{}", tile.number)
+                };
+                let prev_y = self.y_pos;
+
+                self.y_pos = y_pos;
+                self.draw_box(8, 2, x_pos, false, text.as_str())?;
+                self.y_pos = prev_y;
+            }
+            TileStyle::Compact => {
+                let text = if tile.is_wildcard {
+                    format!("w{}", tile.number)
+                } else {
+                    format!("{}", tile.number)
+                };
+
+                self.move_to(x_pos, y_pos)?;
+                self.print(&text);
+            }
+        }
+
         self.execute(ResetColor)?;
 
         Ok(())
     }
 }
+
+fn as_color(color: TileColor) -> Color {
+    match color {
+        TileColor::Blue => Color::Blue,
+        TileColor::Red => Color::Red,
+        TileColor::Orange => Color::Rgb {
+            r: 232,
+            g: 118,
+            b: 0,
+        },
+        TileColor::Black => Color::Black,
+    }
+}
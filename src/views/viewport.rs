@@ -0,0 +1,144 @@
+use crossterm::event::KeyCode;
+
+/// The rows of a page that fit on the terminal from row `top` on, the content below `top` is
+/// shown from `offset` on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub top: u16,
+    pub offset: u16,
+    pub height: u16,
+    pub content: u16,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self::new(u16::MAX)
+    }
+}
+
+impl Viewport {
+    pub fn new(height: u16) -> Self {
+        Viewport {
+            top: 0,
+            offset: 0,
+            height,
+            content: 0,
+        }
+    }
+
+    /// The terminal row of page row `y`, `None` when it is scrolled out of view.
+    pub fn row(&self, y: u16) -> Option<u16> {
+        y.checked_sub(self.top + self.offset)
+            .filter(|row| *row < self.height)
+            .map(|row| row + self.top)
+    }
+
+    pub fn max_offset(&self) -> u16 {
+        self.content.saturating_sub(self.height)
+    }
+
+    pub fn is_scrollable(&self) -> bool {
+        self.max_offset() > 0
+    }
+
+    pub fn scroll(&mut self, rows: i32) {
+        self.offset = (self.offset as i32 + rows).clamp(0, self.max_offset() as i32) as u16;
+    }
+
+    /// Keep the offset within the content after it shrank, returns true if it moved.
+    pub fn clamp(&mut self) -> bool {
+        let offset = self.offset;
+        self.scroll(0);
+
+        offset != self.offset
+    }
+
+    /// Scroll for the arrow keys, PgUp and PgDn, returns false for any other key.
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        let page = self.height.max(1) as i32;
+
+        match code {
+            KeyCode::Up => self.scroll(-1),
+            KeyCode::Down => self.scroll(1),
+            KeyCode::PageUp => self.scroll(-page),
+            KeyCode::PageDown => self.scroll(page),
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// A status line such as `rows 11-30 of 42`, empty when everything fits.
+    pub fn status(&self) -> String {
+        if !self.is_scrollable() {
+            return String::new();
+        }
+
+        format!(
+            "-- rows {}-{} of {}, scroll with Up/Down and PgUp/PgDn --",
+            self.offset + 1,
+            (self.offset + self.height).min(self.content),
+            self.content
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row() {
+        let mut viewport = Viewport::new(10);
+        viewport.content = 25;
+
+        assert_eq!(viewport.row(0), Some(0));
+        assert_eq!(viewport.row(10), None);
+
+        viewport.scroll(5);
+        assert_eq!(viewport.row(4), None);
+        assert_eq!(viewport.row(5), Some(0));
+        assert_eq!(viewport.row(14), Some(9));
+        assert_eq!(viewport.row(15), None);
+
+        // the rows above the top stay where they are
+        viewport.top = 3;
+        assert_eq!(viewport.row(7), None);
+        assert_eq!(viewport.row(8), Some(3));
+        assert_eq!(viewport.row(17), Some(12));
+        assert_eq!(viewport.row(18), None);
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut viewport = Viewport::new(10);
+        viewport.content = 25;
+
+        assert!(viewport.handle_key(KeyCode::PageDown));
+        assert_eq!(viewport.offset, 10);
+        viewport.handle_key(KeyCode::PageDown);
+        assert_eq!(viewport.offset, 15);
+        assert_eq!(
+            viewport.status(),
+            "-- rows 16-25 of 25, scroll with Up/Down and PgUp/PgDn --"
+        );
+
+        viewport.handle_key(KeyCode::Up);
+        assert_eq!(viewport.offset, 14);
+        viewport.handle_key(KeyCode::PageUp);
+        viewport.handle_key(KeyCode::PageUp);
+        assert_eq!(viewport.offset, 0);
+        assert!(!viewport.handle_key(KeyCode::Char('k')));
+
+        // deleting sets shrinks the content under the offset
+        viewport.scroll(12);
+        viewport.content = 15;
+        assert!(viewport.clamp());
+        assert_eq!(viewport.offset, 5);
+
+        viewport.content = 8;
+        viewport.clamp();
+        assert_eq!(viewport.offset, 0);
+        assert_eq!(viewport.status(), "");
+    }
+}